@@ -0,0 +1,10 @@
+-- Your SQL goes here
+create table crawl_state(
+    media varchar(100) not null,
+    feed varchar(100) not null,
+    last_item_id varchar(100),
+    next_page_token varchar(255),
+    last_crawled_at datetime,
+    item_count int not null default 0,
+    primary key (media, feed)
+);
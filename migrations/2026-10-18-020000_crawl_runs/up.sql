@@ -0,0 +1,9 @@
+-- Your SQL goes here
+create table crawl_runs(
+    id varchar(100) Primary key,
+    media varchar(100) not null,
+    started_at datetime not null,
+    finished_at datetime,
+    error text,
+    index media_started_at_index(media, started_at)
+);
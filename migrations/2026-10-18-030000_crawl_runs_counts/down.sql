@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+alter table crawl_runs
+    drop column pages_fetched,
+    drop column articles_inserted,
+    drop column articles_updated;
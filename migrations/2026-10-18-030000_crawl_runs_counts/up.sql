@@ -0,0 +1,5 @@
+-- Your SQL goes here
+alter table crawl_runs
+    add column pages_fetched int not null default 0,
+    add column articles_inserted int not null default 0,
+    add column articles_updated int not null default 0;
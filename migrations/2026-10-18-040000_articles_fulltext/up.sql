@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- 日本語のtitleも引けるようにngram parserを使う。
+alter table articles
+    add fulltext index fulltext_index(title, summary, author) with parser ngram;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+update articles set media = 'media' where media = 'youtube';
+update articles set media = 'Qiita' where media = 'qiita';
+update crawl_state set media = concat(upper(left(media, 1)), substring(media, 2));
+update crawl_runs set media = concat(upper(left(media, 1)), substring(media, 2));
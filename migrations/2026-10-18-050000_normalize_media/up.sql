@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- youtubeはplaceholderの"media"で保存されていた。
+update articles set media = 'youtube' where media in ('media', 'Youtube');
+update articles set media = 'qiita' where media = 'Qiita';
+update articles set media = 'twitter' where media = 'Twitter';
+update crawl_state set media = lower(media);
+update crawl_runs set media = lower(media);
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+update articles set created_at = convert_tz(created_at, '+00:00', '+09:00') where media = 'qiita';
+update articles set crawled_at = convert_tz(crawled_at, '+00:00', 'SYSTEM');
+update crawl_state set last_crawled_at = convert_tz(last_crawled_at, '+00:00', 'SYSTEM');
+update crawl_runs set
+    started_at = convert_tz(started_at, '+00:00', 'SYSTEM'),
+    finished_at = convert_tz(finished_at, '+00:00', 'SYSTEM');
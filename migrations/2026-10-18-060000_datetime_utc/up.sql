@@ -0,0 +1,9 @@
+-- Your SQL goes here
+-- 日時はUTCで保存する。
+-- qiitaのcreated_atは+09:00のまま、crawlした時刻はserverのlocal timeで保存されていた。
+update articles set created_at = convert_tz(created_at, '+09:00', '+00:00') where media = 'qiita';
+update articles set crawled_at = convert_tz(crawled_at, 'SYSTEM', '+00:00');
+update crawl_state set last_crawled_at = convert_tz(last_crawled_at, 'SYSTEM', '+00:00');
+update crawl_runs set
+    started_at = convert_tz(started_at, 'SYSTEM', '+00:00'),
+    finished_at = convert_tz(finished_at, 'SYSTEM', '+00:00');
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+alter table crawl_state
+    drop column etag,
+    drop column last_modified,
+    modify feed varchar(100) not null,
+    modify last_item_id varchar(100);
+alter table articles
+    modify id varchar(100) not null,
+    modify title varchar(100) not null,
+    modify url varchar(100) not null,
+    modify summary varchar(100) not null;
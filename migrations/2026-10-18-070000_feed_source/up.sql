@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- feedのguidやurl、summaryは100文字に収まらない。
+alter table articles
+    modify id varchar(255) not null,
+    modify title varchar(255) not null,
+    modify url varchar(255) not null,
+    modify summary text not null;
+alter table crawl_state
+    modify feed varchar(255) not null,
+    modify last_item_id varchar(255),
+    add column etag varchar(255),
+    add column last_modified varchar(100);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+alter table articles
+    drop column discussion_url,
+    drop column score,
+    drop column comment_count;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+alter table articles
+    add column discussion_url varchar(255),
+    add column score int,
+    add column comment_count int;
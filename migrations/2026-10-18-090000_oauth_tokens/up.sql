@@ -0,0 +1,7 @@
+-- Your SQL goes here
+create table oauth_tokens(
+    media varchar(100) not null primary key,
+    access_token text not null,
+    refresh_token text,
+    expires_at datetime not null
+);
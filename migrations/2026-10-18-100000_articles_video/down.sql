@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+alter table articles
+    drop column thumbnail_url,
+    drop column duration_seconds,
+    drop column view_count,
+    drop column unavailable;
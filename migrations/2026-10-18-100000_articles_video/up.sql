@@ -0,0 +1,6 @@
+-- Your SQL goes here
+alter table articles
+    add column thumbnail_url varchar(255),
+    add column duration_seconds int,
+    add column view_count bigint,
+    add column unavailable boolean not null default false;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter table articles add column metadata text;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+delete from crawl_state where account_id <> '';
+alter table crawl_state drop primary key, add primary key (media, feed);
+alter table crawl_state drop column account_id;
+alter table articles drop column account_id;
+drop table sources;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+create table sources(
+    id varchar(100) not null primary key,
+    media varchar(100) not null,
+    label varchar(255) not null,
+    account varchar(255) not null,
+    credential text,
+    created_at datetime not null
+);
+alter table articles add column account_id varchar(100);
+-- 環境変数で設定したaccountは空文字。
+alter table crawl_state add column account_id varchar(100) not null default '' after media;
+alter table crawl_state drop primary key, add primary key (media, account_id, feed);
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+create table tags(
+    id bigint not null auto_increment primary key,
+    name varchar(100) not null,
+    unique index name_index (name)
+);
+create table article_tags(
+    article_id varchar(255) not null,
+    tag_id bigint not null,
+    primary key (article_id, tag_id),
+    index tag_id_index (tag_id)
+);
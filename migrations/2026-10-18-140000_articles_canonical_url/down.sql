@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+drop index canonical_url_index on articles;
+alter table articles drop column canonical_url;
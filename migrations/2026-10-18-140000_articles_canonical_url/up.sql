@@ -0,0 +1,4 @@
+-- Your SQL goes here
+-- 既存のrowは起動時にurlから埋める。
+alter table articles add column canonical_url varchar(255) not null default '';
+create index canonical_url_index on articles (canonical_url);
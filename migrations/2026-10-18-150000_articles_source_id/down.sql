@@ -0,0 +1,22 @@
+-- This file should undo anything in `up.sql`
+drop index media_source_id_index on articles;
+update crawl_state set last_item_id = case
+    when media = 'hatena' then concat('hatena:', last_item_id)
+    when media = 'github' then concat('github:', last_item_id)
+    when media = 'mastodon' then concat('mastodon:', last_item_id)
+    when media = 'hackernews' then concat('hn:', last_item_id)
+    when media = 'reddit' then concat('reddit:', last_item_id)
+    else last_item_id
+end
+where last_item_id is not null;
+update articles set id = case
+    when media = 'hatena' then concat('hatena:', source_id)
+    when media = 'github' then concat('github:', source_id)
+    when media = 'mastodon' then concat('mastodon:', source_id)
+    when media = 'hackernews' then concat('hn:', source_id)
+    when media = 'reddit' then concat('reddit:', source_id)
+    else source_id
+end;
+update article_tags t join articles a on t.article_id = concat(a.media, ':', a.source_id)
+    set t.article_id = a.id;
+alter table articles drop column source_id;
@@ -0,0 +1,27 @@
+-- Your SQL goes here
+-- idをmedia:source_idにする。独自のprefixをつけていたmediaはprefixを外してsource_idにする。
+alter table articles
+    modify id varchar(300) not null,
+    add column source_id varchar(255) not null default '';
+alter table article_tags modify article_id varchar(300) not null;
+update articles set source_id = case
+    when media = 'hatena' and id like 'hatena:%' then substring(id, 8)
+    when media = 'github' and id like 'github:%' then substring(id, 8)
+    when media = 'mastodon' and id like 'mastodon:%' then substring(id, 10)
+    when media = 'hackernews' and id like 'hn:%' then substring(id, 4)
+    when media = 'reddit' and id like 'reddit:%' then substring(id, 8)
+    else id
+end;
+update article_tags t join articles a on t.article_id = a.id
+    set t.article_id = concat(a.media, ':', a.source_id);
+update articles set id = concat(media, ':', source_id);
+-- 差分crawlの目印もsource_idにそろえる。
+update crawl_state set last_item_id = case
+    when media = 'hatena' and last_item_id like 'hatena:%' then substring(last_item_id, 8)
+    when media = 'github' and last_item_id like 'github:%' then substring(last_item_id, 8)
+    when media = 'mastodon' and last_item_id like 'mastodon:%' then substring(last_item_id, 10)
+    when media = 'hackernews' and last_item_id like 'hn:%' then substring(last_item_id, 4)
+    when media = 'reddit' and last_item_id like 'reddit:%' then substring(last_item_id, 8)
+    else last_item_id
+end;
+create unique index media_source_id_index on articles (media, source_id);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+alter table crawl_runs drop column articles_unchanged;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter table crawl_runs add column articles_unchanged int not null default 0;
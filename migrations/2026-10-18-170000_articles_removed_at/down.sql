@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+alter table crawl_runs drop column articles_removed;
+alter table articles drop column removed_at;
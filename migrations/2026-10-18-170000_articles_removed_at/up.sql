@@ -0,0 +1,3 @@
+-- Your SQL goes here
+alter table articles add column removed_at datetime;
+alter table crawl_runs add column articles_removed int not null default 0;
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+update crawl_state set last_item_id = substring(last_item_id, char_length(feed) + 2)
+    where media = 'feed' and left(last_item_id, char_length(feed) + 1) = concat(feed, '#');
+alter table crawl_state modify last_item_id varchar(255);
+alter table article_tags modify article_id varchar(300) not null;
+alter table articles
+    modify id varchar(300) not null,
+    modify source_id varchar(255) not null default '';
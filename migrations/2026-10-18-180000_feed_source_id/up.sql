@@ -0,0 +1,11 @@
+-- Your SQL goes here
+-- feedのsource_idにはfeedのurlをつけるので広げる。
+alter table articles
+    modify id varchar(530) not null,
+    modify source_id varchar(512) not null default '';
+alter table article_tags modify article_id varchar(530) not null;
+alter table crawl_state modify last_item_id varchar(512);
+-- 差分crawlの目印はfeedが分かるのでurlをつける。
+-- 保存済みのarticleはどのfeedのものか分からないのでそのまま。
+update crawl_state set last_item_id = concat(feed, '#', last_item_id)
+    where media = 'feed' and last_item_id is not null;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+alter table articles add column account_id varchar(100);
+update articles a set account_id = (
+    select min(nullif(t.account_id, '')) from article_accounts t where t.article_id = a.id
+);
+drop table article_accounts;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- 同じarticleを複数のaccountが保存するので、accountごとに記録する。
+-- 環境変数で設定したaccountは空文字。
+create table article_accounts(
+    article_id varchar(530) not null,
+    account_id varchar(100) not null default '',
+    primary key (article_id, account_id),
+    index account_id_index (account_id)
+);
+insert into article_accounts (article_id, account_id)
+    select id, coalesce(account_id, '') from articles;
+alter table articles drop column account_id;
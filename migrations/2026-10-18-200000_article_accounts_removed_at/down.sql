@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+alter table article_accounts drop column removed_at;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+-- accountごとに外されたかを記録する。全てのaccountで外されたらarticlesにも記録する。
+alter table article_accounts add column removed_at datetime null;
+update article_accounts t join articles a on t.article_id = a.id
+    set t.removed_at = a.removed_at
+    where a.removed_at is not null;
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- t.coなどを展開した先のurlは255文字を超えることがある。
+alter table articles modify url varchar(2048) not null;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+drop index canonical_url_index on articles;
+update articles set canonical_url = '' where char_length(canonical_url) > 255;
+alter table articles modify canonical_url varchar(255) not null default '';
+create index canonical_url_index on articles (canonical_url);
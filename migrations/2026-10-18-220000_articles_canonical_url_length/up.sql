@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- urlと同じ長さにする。indexは先頭だけで足りる。
+drop index canonical_url_index on articles;
+alter table articles modify canonical_url varchar(2048) not null default '';
+create index canonical_url_index on articles (canonical_url(255));
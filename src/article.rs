@@ -1,38 +1,287 @@
 use std::{str::FromStr, string::ParseError};
 
-use async_graphql::SimpleObject;
-use chrono::NaiveDateTime;
+use crate::crawl::Media;
+use crate::store;
+use crate::utils::db::DbPool;
+use crate::utils::errors::MyError;
+use async_graphql::{ComplexObject, Context, Enum, InputObject, SimpleObject};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use strum_macros::{Display, EnumString};
 
+/// titleのvarchar(255)に収まるように切る。
+pub const TITLE_MAX_CHARS: usize = 255;
+
 #[derive(Serialize, Deserialize, Debug, SimpleObject, Clone, PartialEq)]
+#[graphql(complex)]
 pub struct Article {
+    /// media:source_idの形で、mediaをまたいで一意。
     pub id: String,
+    /// 元のsourceでのid
+    pub source_id: String,
     pub title: String,
     pub author: String,
-    pub media: String,
+    pub media: Media,
     pub url: String,
+    /// 別のmediaから保存した同じページで同じになるurl。保存するときに決まる。
+    pub canonical_url: Option<String>,
     pub summary: String,
-    pub created_at: String,
-    pub crawled_at: String,
+    pub created_at: DateTime<Utc>,
+    pub crawled_at: DateTime<Utc>,
+    /// hacker news、redditなどでurlとは別にある議論のページ
+    pub discussion_url: Option<String>,
+    /// 元のsourceでの点数
+    pub score: Option<i32>,
+    pub comment_count: Option<i32>,
+    pub thumbnail_url: Option<String>,
+    /// 動画の長さ
+    pub duration_seconds: Option<i32>,
+    pub view_count: Option<i64>,
+    /// 削除、非公開になっていて元のsourceで見られない。
+    pub unavailable: bool,
+    pub metadata: Option<ArticleMetadata>,
+    /// qiitaのtag、githubのtopicなど。sourceにtagがなければ[]
+    pub tags: Vec<String>,
+    /// 取得したaccountのsources id。保存するときにarticle_accountsへ記録する。
+    /// 環境変数で設定したaccountはNone。
+    #[graphql(skip)]
+    pub account_id: Option<String>,
+    /// 保存したaccountのsources id。環境変数で設定したaccountは含まない。
+    pub account_ids: Vec<String>,
+    /// 元のsourceでいいねやstockが外された日時。
+    pub removed_at: Option<DateTime<Utc>>,
+    /// 同じcanonical urlのほかのarticle。一覧でまとめて読み込んだときはSome。
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub same_page: Option<Vec<Article>>,
+}
+
+#[ComplexObject]
+impl Article {
+    /// 同じページを保存したmedia。このarticleのmediaを含む。
+    async fn saved_from(&self, ctx: &Context<'_>) -> Result<Vec<Media>, MyError> {
+        let mut medias = vec![self.media];
+        for article in self.same_page(ctx)? {
+            if !medias.contains(&article.media) {
+                medias.push(article.media);
+            }
+        }
+        Ok(medias)
+    }
+
+    /// 別のmediaやaccountから保存した同じページ。
+    async fn duplicates(&self, ctx: &Context<'_>) -> Result<Vec<Article>, MyError> {
+        self.same_page(ctx)
+    }
+}
+
+impl Article {
+    /// sourceによってはない項目は空にする。struct更新構文で必要なものだけ上書きする。
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        media: Media,
+        source_id: String,
+        title: String,
+        author: String,
+        url: String,
+        summary: String,
+        created_at: DateTime<Utc>,
+        crawled_at: DateTime<Utc>,
+    ) -> Self {
+        Article {
+            id: Article::id_of(media, &source_id),
+            source_id,
+            title,
+            author,
+            media,
+            url,
+            canonical_url: None,
+            summary,
+            created_at,
+            crawled_at,
+            discussion_url: None,
+            score: None,
+            comment_count: None,
+            thumbnail_url: None,
+            duration_seconds: None,
+            view_count: None,
+            unavailable: false,
+            metadata: None,
+            tags: vec![],
+            account_id: None,
+            account_ids: vec![],
+            removed_at: None,
+            same_page: None,
+        }
+    }
+
+    /// source_idはmediaが違えば衝突しうるので、mediaで名前空間を分ける。
+    pub fn id_of(media: Media, source_id: &str) -> String {
+        format!("{}:{}", media, source_id)
+    }
+
+    /// 同じcanonical urlのほかのarticle。読み込み済みでなければDBから取得する。
+    fn same_page(&self, ctx: &Context<'_>) -> Result<Vec<Article>, MyError> {
+        if let Some(same_page) = &self.same_page {
+            return Ok(same_page.clone());
+        }
+        let canonical_url = match &self.canonical_url {
+            Some(canonical_url) if !canonical_url.is_empty() => canonical_url,
+            _ => return Ok(vec![]),
+        };
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
+        let articles = store::model::find_by_canonical_url(&conn, canonical_url)?;
+        Ok(articles
+            .into_iter()
+            .filter(|article| article.id != self.id)
+            .collect())
+    }
+}
+
+/// 引用や添付など、sourceによってはない情報。DBにはjsonで保存する。
+#[derive(Serialize, Deserialize, Debug, SimpleObject, Clone, PartialEq, Default)]
+pub struct ArticleMetadata {
+    pub quoted: Option<QuotedPost>,
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Serialize, Deserialize, Debug, SimpleObject, Clone, PartialEq)]
+pub struct QuotedPost {
+    pub id: String,
+    pub author: String,
+    pub text: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, SimpleObject, Clone, PartialEq)]
+pub struct Attachment {
+    /// photo、video、animated_gifなど
+    pub kind: String,
+    pub url: Option<String>,
+    pub preview_url: Option<String>,
+    pub alt_text: Option<String>,
+}
+
+/// articles queryの絞り込み条件。Noneの条件では絞り込まない。
+/// tagsはtagMatchがALLなら全て、ANYならどれかのtagがついたもの。
+#[derive(InputObject, Debug, Clone, Default, PartialEq)]
+pub struct ArticleFilter {
+    pub media: Option<Media>,
+    pub author: Option<String>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    /// 空なら絞り込まない。
+    #[graphql(default)]
+    pub tags: Vec<String>,
+    #[graphql(default)]
+    pub tag_match: TagMatch,
+    /// falseならremoved_atのあるarticleを除く。
+    #[graphql(default)]
+    pub include_removed: bool,
+    /// trueなら同じcanonical urlのarticleは最初に保存した1件にまとめる。
+    #[graphql(default)]
+    pub group_duplicates: bool,
+}
+
+/// tagsでの絞り込み方
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagMatch {
+    /// 全てのtagがついている。
+    #[default]
+    All,
+    /// どれかのtagがついている。
+    Any,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArticleOrder {
+    #[default]
+    CreatedAtDesc,
+    CreatedAtAsc,
+}
+
+/// 前後の空白を除き、重複と空のtagを除く。
+pub fn normalize_tags<I: IntoIterator<Item = String>>(tags: I) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            normalized.push(tag);
+        }
+    }
+    normalized
 }
 
 pub struct DatetimeFormatter {}
 
+/// 各sourceのapiが返す日時をUTCにする。
+/// 形式が違っても落とさずにerrorを返す。
 impl DatetimeFormatter {
-    pub fn qiita_to(datetime: &String) -> String {
-        let naive_datetime =
-            NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S%:z").unwrap();
-        naive_datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-    }
-    pub fn youtube_to(datetime: &String) -> String {
-        let naive_datetime =
-            NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S%Z").unwrap();
-        naive_datetime.format("%Y-%m-%d %H:%M:%S").to_string()
-    }
-    pub fn twitter_to(datetime: &String) -> String {
-        let naive_datetime =
-            NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S.%Z").unwrap();
-        naive_datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+    /// 2022-12-01T12:34:56+09:00
+    pub fn qiita_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// 2022-12-01T12:34:56Z
+    pub fn youtube_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// 2022-12-01T12:34:56.000Z
+    pub fn twitter_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// 2022-12-01T12:34:56.789+09:00
+    pub fn zenn_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// 2022-12-01T12:34:56+09:00
+    pub fn hatena_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// 2022-12-01T12:34:56Z
+    pub fn github_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// 2022-12-01T12:34:56.000Z
+    pub fn mastodon_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// 2022-12-01T12:34:56.000Z
+    pub fn bluesky_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        Self::parse_rfc3339(datetime)
+    }
+    /// unix time
+    pub fn hacker_news_to(timestamp: i64) -> Result<DateTime<Utc>, MyError> {
+        Self::from_timestamp(timestamp)
+    }
+    /// 1669898096.0
+    pub fn reddit_to(timestamp: f64) -> Result<DateTime<Utc>, MyError> {
+        Self::from_timestamp(timestamp as i64)
+    }
+    /// RSSはSat, 01 Dec 2022 12:34:56 +0900。RFC3339で書くfeedもある。
+    pub fn feed_to(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        DateTime::parse_from_rfc2822(datetime.trim())
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .or_else(|_| Self::parse_rfc3339(datetime.trim()))
+    }
+    /// Atomはparse済み。
+    pub fn atom_to(datetime: &DateTime<FixedOffset>) -> DateTime<Utc> {
+        datetime.with_timezone(&Utc)
+    }
+    fn from_timestamp(timestamp: i64) -> Result<DateTime<Utc>, MyError> {
+        Utc.timestamp_opt(timestamp, 0).single().ok_or_else(|| {
+            MyError::UnprocessableEntity(
+                json!({ "error": format!("invalid timestamp {}", timestamp) }),
+            )
+        })
+    }
+    fn parse_rfc3339(datetime: &str) -> Result<DateTime<Utc>, MyError> {
+        DateTime::parse_from_rfc3339(datetime)
+            .map(|datetime| datetime.with_timezone(&Utc))
+            .map_err(|err| {
+                MyError::UnprocessableEntity(
+                    json!({ "error": format!("invalid datetime {}: {}", datetime, err) }),
+                )
+            })
     }
 }
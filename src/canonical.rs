@@ -0,0 +1,255 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use reqwest::Client;
+use url::Url;
+
+/// 追跡用でページの内容に関係ないquery
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "igshid", "ref_src", "ref_url"];
+/// redirectを辿らないと行き先がわからないhost
+const SHORTENER_HOSTS: &[&str] = &["t.co", "bit.ly", "buff.ly", "ow.ly", "dlvr.it"];
+const MAX_REDIRECTS: usize = 5;
+/// 応答しない短縮urlでcrawlが止まらないようにする。
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 別のmediaから保存した同じページを同じurlにする。
+/// urlとして解釈できなければそのまま返す。
+pub fn canonicalize(url: &str) -> String {
+    let url = match Url::parse(url.trim()) {
+        Ok(url) if url.has_host() => url,
+        _ => return url.trim().to_string(),
+    };
+    let host = normalize_host(url.host_str().unwrap_or_default());
+    if let Some(video_id) = youtube_video_id(&host, &url) {
+        return format!("https://youtube.com/watch?v={}", video_id);
+    }
+    // tweetのqueryは共有元の情報だけ。
+    let pairs = url
+        .query_pairs()
+        .filter(|(key, _)| host != "twitter.com" && !is_tracking_param(key))
+        .collect::<Vec<_>>();
+    let port = url
+        .port()
+        .map(|port| format!(":{}", port))
+        .unwrap_or_default();
+    let path = url.path().trim_end_matches('/');
+    let mut canonical = format!("https://{}{}{}", host, port, path);
+    if !pairs.is_empty() {
+        canonical.push('?');
+        canonical.push_str(
+            &url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(pairs)
+                .finish(),
+        );
+    }
+    canonical
+}
+
+/// t.coなどの短縮urlか。
+pub fn is_shortened(url: &str) -> bool {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+        .is_some_and(|host| SHORTENER_HOSTS.contains(&host.as_str()))
+}
+
+/// redirectは自分で辿るので、自動では辿らないclient。
+fn resolve_client() -> Option<&'static Client> {
+    static CLIENT: OnceLock<Option<Client>> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            Client::builder()
+                .redirect(Policy::none())
+                .timeout(RESOLVE_TIMEOUT)
+                .build()
+                .ok()
+        })
+        .as_ref()
+}
+
+/// 短縮urlのredirectを辿った先。辿れなければ元のurl。
+pub async fn resolve(url: &str) -> String {
+    let client = match resolve_client() {
+        Some(client) => client,
+        None => return url.to_string(),
+    };
+    let mut current = url.to_string();
+    // 短縮urlが短縮urlを指していることもある。
+    for _ in 0..MAX_REDIRECTS {
+        if !is_shortened(&current) {
+            break;
+        }
+        let location = match client.head(&current).send().await {
+            Ok(res) => res
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .and_then(|location| redirect_target(&current, location)),
+            Err(err) => {
+                log::warn!("failed to resolve {}: {:?}", current, err);
+                None
+            }
+        };
+        match location {
+            Some(location) => current = location,
+            None => break,
+        }
+    }
+    current
+}
+
+/// Locationは相対urlのこともあるので、今のurlからの位置にする。
+fn redirect_target(current: &str, location: &str) -> Option<String> {
+    Some(Url::parse(current).ok()?.join(location).ok()?.to_string())
+}
+
+/// www.やm.を除き、twitterはx.comとtwitter.comをまとめる。
+fn normalize_host(host: &str) -> String {
+    let host = host.to_lowercase();
+    let host = host
+        .strip_prefix("www.")
+        .or(host.strip_prefix("m."))
+        .or(host.strip_prefix("mobile."))
+        .unwrap_or(&host);
+    match host {
+        "x.com" => "twitter.com".to_string(),
+        _ => host.to_string(),
+    }
+}
+
+fn is_tracking_param(key: &str) -> bool {
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key)
+}
+
+/// youtu.be/ID、youtube.com/watch?v=ID、youtube.com/shorts/IDの動画id。
+fn youtube_video_id(host: &str, url: &Url) -> Option<String> {
+    let mut segments = url.path_segments()?.filter(|segment| !segment.is_empty());
+    let video_id = match host {
+        "youtu.be" => segments.next().map(|id| id.to_string()),
+        "youtube.com" => match segments.next() {
+            Some("watch") => url
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, value)| value.to_string()),
+            Some("shorts") => segments.next().map(|id| id.to_string()),
+            _ => None,
+        },
+        _ => None,
+    };
+    video_id.filter(|id| !id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_utm_and_tracking_params() {
+        assert_eq!(
+            canonicalize("https://example.com/post?id=1&utm_source=twitter&utm_medium=social"),
+            "https://example.com/post?id=1"
+        );
+        assert_eq!(
+            canonicalize("https://example.com/post?fbclid=abc&gclid=def&ref_src=twsrc"),
+            "https://example.com/post"
+        );
+    }
+
+    #[test]
+    fn keeps_other_params_in_order() {
+        assert_eq!(
+            canonicalize("https://example.com/search?q=rust+lang&page=2"),
+            "https://example.com/search?q=rust+lang&page=2"
+        );
+    }
+
+    #[test]
+    fn normalizes_scheme_host_and_fragment() {
+        assert_eq!(
+            canonicalize("http://WWW.Example.com/post#comments"),
+            "https://example.com/post"
+        );
+        assert_eq!(
+            canonicalize("https://m.example.com/post"),
+            "https://example.com/post"
+        );
+        assert_eq!(
+            canonicalize("https://mobile.twitter.com/rustlang/status/1"),
+            "https://twitter.com/rustlang/status/1"
+        );
+        assert_eq!(
+            canonicalize("https://example.com:8080/post"),
+            "https://example.com:8080/post"
+        );
+    }
+
+    #[test]
+    fn maps_x_to_twitter_and_drops_tweet_query() {
+        assert_eq!(
+            canonicalize("https://x.com/rustlang/status/1?s=20&t=abc"),
+            "https://twitter.com/rustlang/status/1"
+        );
+    }
+
+    #[test]
+    fn trims_trailing_slash() {
+        assert_eq!(
+            canonicalize("https://example.com/blog/post/"),
+            "https://example.com/blog/post"
+        );
+        assert_eq!(canonicalize("https://example.com/"), "https://example.com");
+    }
+
+    #[test]
+    fn unifies_youtube_video_urls() {
+        let canonical = "https://youtube.com/watch?v=dQw4w9WgXcQ";
+        assert_eq!(canonicalize("https://youtu.be/dQw4w9WgXcQ?t=42"), canonical);
+        assert_eq!(
+            canonicalize("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1&index=2"),
+            canonical
+        );
+        assert_eq!(
+            canonicalize("https://m.youtube.com/watch?feature=share&v=dQw4w9WgXcQ"),
+            canonical
+        );
+        assert_eq!(
+            canonicalize("https://youtube.com/shorts/dQw4w9WgXcQ"),
+            canonical
+        );
+    }
+
+    #[test]
+    fn keeps_youtube_pages_that_are_not_videos() {
+        assert_eq!(
+            canonicalize("https://www.youtube.com/@rustlang/"),
+            "https://youtube.com/@rustlang"
+        );
+    }
+
+    #[test]
+    fn returns_unparsable_url_as_is() {
+        assert_eq!(canonicalize(" not a url "), "not a url");
+    }
+
+    #[test]
+    fn detects_shortened_urls() {
+        assert!(is_shortened("https://t.co/abc123"));
+        assert!(is_shortened("https://bit.ly/abc123"));
+        assert!(!is_shortened("https://twitter.com/rustlang/status/1"));
+        assert!(!is_shortened("not a url"));
+    }
+
+    #[test]
+    fn joins_relative_location_to_current_url() {
+        assert_eq!(
+            redirect_target("https://bit.ly/abc", "/xyz").as_deref(),
+            Some("https://bit.ly/xyz")
+        );
+        assert_eq!(
+            redirect_target("https://t.co/abc", "https://example.com/post").as_deref(),
+            Some("https://example.com/post")
+        );
+    }
+}
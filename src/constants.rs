@@ -1,3 +1,26 @@
 pub mod env_key {
     pub const DATABASE_URL: &str = "DATABASE_URL";
+    pub const QIITA_ACCESS_TOKEN: &str = "QIITA_ACCESS_TOKEN";
+    pub const QIITA_USER_ID: &str = "QIITA_USER_ID";
+    pub const YOUTUBE_API_KEY: &str = "YOUTUBE_API_KEY";
+    pub const YOUTUBE_CHANNEL_ID: &str = "YOUTUBE_CHANNEL_ID";
+    pub const YOUTUBE_OAUTH_CLIENT_ID: &str = "YOUTUBE_OAUTH_CLIENT_ID";
+    pub const YOUTUBE_OAUTH_CLIENT_SECRET: &str = "YOUTUBE_OAUTH_CLIENT_SECRET";
+    pub const YOUTUBE_OAUTH_REDIRECT_URI: &str = "YOUTUBE_OAUTH_REDIRECT_URI";
+    pub const TWITTER_BEARER_TOKEN: &str = "TWITTER_BEARER_TOKEN";
+    pub const TWITTER_USER_ID: &str = "TWITTER_USER_ID";
+    pub const ZENN_USER_NAME: &str = "ZENN_USER_NAME";
+    pub const FEED_URLS: &str = "FEED_URLS";
+    pub const HATENA_USER_ID: &str = "HATENA_USER_ID";
+    pub const GITHUB_USER: &str = "GITHUB_USER";
+    pub const GITHUB_TOKEN: &str = "GITHUB_TOKEN";
+    pub const MASTODON_INSTANCE_URL: &str = "MASTODON_INSTANCE_URL";
+    pub const MASTODON_ACCESS_TOKEN: &str = "MASTODON_ACCESS_TOKEN";
+    pub const BLUESKY_IDENTIFIER: &str = "BLUESKY_IDENTIFIER";
+    pub const BLUESKY_APP_PASSWORD: &str = "BLUESKY_APP_PASSWORD";
+    pub const HACKER_NEWS_USER_ID: &str = "HACKER_NEWS_USER_ID";
+    pub const REDDIT_CLIENT_ID: &str = "REDDIT_CLIENT_ID";
+    pub const REDDIT_CLIENT_SECRET: &str = "REDDIT_CLIENT_SECRET";
+    pub const REDDIT_USERNAME: &str = "REDDIT_USERNAME";
+    pub const REDDIT_PASSWORD: &str = "REDDIT_PASSWORD";
 }
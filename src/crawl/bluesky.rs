@@ -0,0 +1,204 @@
+use crate::article::{Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{self, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+use super::{credential_or_env, env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
+
+const BLUESKY_XRPC_BASE_URL: &str = "https://bsky.social/xrpc/";
+const BLUESKY_WEB_BASE_URL: &str = "https://bsky.app";
+
+#[derive(Debug, Clone)]
+pub struct BlueskyCrawler {
+    client: Client,
+    /// handleかdid
+    identifier: String,
+    app_password: String,
+}
+
+impl BlueskyCrawler {
+    pub fn new(identifier: String, app_password: String) -> Self {
+        let client = reqwest::Client::new();
+        BlueskyCrawler {
+            client,
+            identifier,
+            app_password,
+        }
+    }
+
+    /// getActorLikesは本人のlikeしか取得できないのでsessionを作る。
+    async fn create_session(&self) -> Result<Session, MyError> {
+        let session = self
+            .client
+            .post(format!(
+                "{}com.atproto.server.createSession",
+                BLUESKY_XRPC_BASE_URL
+            ))
+            .json(&json!({
+                "identifier": self.identifier,
+                "password": self.app_password,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Session>()
+            .await?;
+        Ok(session)
+    }
+
+    /// likeの新しい順にcursorで辿る。
+    /// stateのlast_item_id(前回見えた最新のlike)に到達したらそれ以降は取得しない。
+    async fn fetch_until(&self, state: CrawlState) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let session = self.create_session().await?;
+        let mut cursor: Option<String> = None;
+        let mut articles = vec![];
+        let mut partial = false;
+        let mut pages = 0;
+        'crawl: loop {
+            let mut query_params =
+                vec![("actor", session.did.clone()), ("limit", "100".to_string())];
+            if let Some(cursor) = cursor {
+                query_params.push(("cursor", cursor));
+            }
+            let likes = self
+                .client
+                .get(format!(
+                    "{}app.bsky.feed.getActorLikes",
+                    BLUESKY_XRPC_BASE_URL
+                ))
+                .query(&query_params)
+                .bearer_auth(&session.access_jwt)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<LikesRes>()
+                .await?;
+            pages += 1;
+            for article in likes.feed.iter().filter_map(|item| {
+                skip_invalid(&mut partial, item.post.to_article(self.media(), crawled_at))
+            }) {
+                if Some(&article.source_id) == state.last_item_id.as_ref() {
+                    break 'crawl;
+                }
+                articles.push(article);
+            }
+            // 最後のpageでもcursorが返ることがある。
+            match likes.cursor {
+                Some(next) if !likes.feed.is_empty() => cursor = Some(next),
+                _ => break,
+            }
+        }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages,
+            partial,
+        })
+    }
+}
+
+#[async_trait]
+impl Crawl for BlueskyCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let identifier = env_var(env_key::BLUESKY_IDENTIFIER)?;
+        let app_password = env_var(env_key::BLUESKY_APP_PASSWORD)?;
+        Ok(BlueskyCrawler::new(identifier, app_password))
+    }
+
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        let app_password = credential_or_env(source, env_key::BLUESKY_APP_PASSWORD)?;
+        Ok(BlueskyCrawler::new(source.account.clone(), app_password))
+    }
+
+    fn media(&self) -> Media {
+        Media::Bluesky
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(15 * 60)
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::new(self.media(), "")).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::find(states, self.media(), ""))
+            .await
+    }
+}
+
+/// bluesky xrpc response schema.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    did: String,
+    access_jwt: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct LikesRes {
+    feed: Vec<FeedItem>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct FeedItem {
+    post: Post,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Post {
+    /// at://did:plc:xxx/app.bsky.feed.post/rkey
+    uri: String,
+    author: Author,
+    record: PostRecord,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Author {
+    did: String,
+    /// didから解決済みのhandle
+    handle: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PostRecord {
+    #[serde(default)]
+    text: String,
+    created_at: String,
+}
+
+impl Post {
+    fn to_article(&self, media: Media, crawled_at: DateTime<Utc>) -> Result<Article, MyError> {
+        let rkey = self.uri.rsplit('/').next().unwrap_or_default();
+        Ok(Article::new(
+            media,
+            self.uri.clone(),
+            self.record.text.clone(),
+            format!("@{}", self.author.handle),
+            format!(
+                "{}/profile/{}/post/{}",
+                BLUESKY_WEB_BASE_URL, self.author.handle, rkey
+            ),
+            // summaryはないので、text.
+            self.record.text.clone(),
+            DatetimeFormatter::bluesky_to(&self.record.created_at)?,
+            crawled_at,
+        ))
+    }
+}
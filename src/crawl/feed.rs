@@ -0,0 +1,256 @@
+use crate::article::{Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{self, Client, StatusCode};
+use serde_json::json;
+
+use super::{env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
+
+/// RSS 2.0とAtomのfeed。feedのurlごとにcrawl stateを持つ。
+#[derive(Debug, Clone)]
+pub struct FeedCrawler {
+    client: Client,
+    urls: Vec<String>,
+}
+
+impl FeedCrawler {
+    pub fn new(urls: Vec<String>) -> Self {
+        let client = reqwest::Client::new();
+        FeedCrawler { client, urls }
+    }
+
+    /// カンマ区切りのurl。nameはerrorに出す設定元。
+    fn from_urls(name: &str, urls: &str) -> Result<Self, MyError> {
+        let urls = urls
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect::<Vec<String>>();
+        if urls.is_empty() {
+            return Err(MyError::BadRequest(
+                json!({ "error": format!("{} is empty", name) }),
+            ));
+        }
+        Ok(FeedCrawler::new(urls))
+    }
+
+    /// 前回のETag、Last-Modifiedで変更がなければ304が返り、何も取得しない。
+    /// 新しい順に並んでいるとみなして、last_item_idに到達したらそれ以降は取得しない。
+    async fn fetch_feed(
+        &self,
+        state: CrawlState,
+        crawled_at: DateTime<Utc>,
+    ) -> Result<Crawled, MyError> {
+        let mut req = self.client.get(&state.feed);
+        if let Some(etag) = &state.etag {
+            req = req.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &state.last_modified {
+            req = req.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let res = req.send().await?;
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Crawled {
+                articles: vec![],
+                states: vec![state.advance(None, None, crawled_at, 0)],
+                pages: 1,
+                partial: false,
+            });
+        }
+        let res = res.error_for_status()?;
+        let header = |name| {
+            res.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let body = res.bytes().await?;
+
+        let mut articles = vec![];
+        let mut partial = false;
+        let parsed =
+            parse(&body, &state.feed, self.media(), crawled_at, &mut partial).map_err(|err| {
+                MyError::UnprocessableEntity(json!({ "error": format!("{}: {}", state.feed, err) }))
+            })?;
+        for article in parsed {
+            if Some(&article.source_id) == state.last_item_id.as_ref() {
+                break;
+            }
+            articles.push(article);
+        }
+        let state = CrawlState {
+            etag,
+            last_modified,
+            ..state.advance(
+                articles.first().map(|article| article.source_id.clone()),
+                None,
+                crawled_at,
+                articles.len(),
+            )
+        };
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages: 1,
+            partial,
+        })
+    }
+
+    async fn fetch_feeds(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let mut crawled = Crawled::default();
+        for url in self.urls.iter() {
+            let state = CrawlState::find(states, self.media(), url);
+            // 1つのfeedが落ちていても他のfeedは取得する。stateは前回のまま。
+            match self.fetch_feed(state, crawled_at).await {
+                Ok(feed_crawled) => crawled.append(feed_crawled),
+                Err(err) => {
+                    log::warn!("failed to fetch feed {}: {:?}", url, err);
+                    crawled.partial = true;
+                }
+            }
+        }
+        Ok(crawled)
+    }
+}
+
+#[async_trait]
+impl Crawl for FeedCrawler {
+    /// FEED_URLSはカンマ区切り。
+    fn from_env() -> Result<Self, MyError> {
+        FeedCrawler::from_urls(env_key::FEED_URLS, &env_var(env_key::FEED_URLS)?)
+    }
+
+    /// accountはカンマ区切りのurl
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        FeedCrawler::from_urls(&source.label, &source.account)
+    }
+
+    fn media(&self) -> Media {
+        Media::Feed
+    }
+
+    /// feedには最新のitemしか載らない。
+    fn is_complete(&self) -> bool {
+        false
+    }
+
+    /// 全件取得。conditional GETはしない。
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_feeds(&[]).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_feeds(states).await
+    }
+}
+
+/// guidはfeedの中でしか一意でない。"1"のような短いguidが衝突しないようにfeedのurlをつける。
+fn source_id(feed_url: &str, id: &str) -> String {
+    format!("{}#{}", feed_url, id)
+}
+
+/// RSSとして読めなければAtomとして読む。
+fn parse(
+    body: &[u8],
+    feed_url: &str,
+    media: Media,
+    crawled_at: DateTime<Utc>,
+    partial: &mut bool,
+) -> Result<Vec<Article>, String> {
+    match rss::Channel::read_from(body) {
+        Ok(channel) => Ok(channel
+            .items()
+            .iter()
+            .filter_map(|item| {
+                rss_to_article(&channel, item, feed_url, media, crawled_at).transpose()
+            })
+            .filter_map(|res| skip_invalid(partial, res))
+            .collect()),
+        Err(_) => {
+            let feed = atom_syndication::Feed::read_from(body).map_err(|err| err.to_string())?;
+            Ok(feed
+                .entries()
+                .iter()
+                .map(|entry| atom_to_article(&feed, entry, feed_url, media, crawled_at))
+                .collect())
+        }
+    }
+}
+
+/// guidもlinkもないitemは識別できないので取り込まない。
+fn rss_to_article(
+    channel: &rss::Channel,
+    item: &rss::Item,
+    feed_url: &str,
+    media: Media,
+    crawled_at: DateTime<Utc>,
+) -> Result<Option<Article>, MyError> {
+    let id = match item.guid().map(|guid| guid.value()).or(item.link()) {
+        Some(id) => id.to_string(),
+        None => return Ok(None),
+    };
+    // authorはメールアドレスのことが多いので、dc:creatorを優先する。
+    let author = item
+        .dublin_core_ext()
+        .and_then(|dc| dc.creators().first().map(|creator| creator.as_str()))
+        .or(item.author())
+        .unwrap_or(channel.title());
+    let created_at = match item.pub_date() {
+        Some(pub_date) => DatetimeFormatter::feed_to(pub_date)?,
+        None => crawled_at,
+    };
+    let url = item.link().unwrap_or(&id).to_string();
+    Ok(Some(Article::new(
+        media,
+        source_id(feed_url, &id),
+        item.title().unwrap_or_default().to_string(),
+        author.to_string(),
+        url,
+        item.description().unwrap_or_default().to_string(),
+        created_at,
+        crawled_at,
+    )))
+}
+
+fn atom_to_article(
+    feed: &atom_syndication::Feed,
+    entry: &atom_syndication::Entry,
+    feed_url: &str,
+    media: Media,
+    crawled_at: DateTime<Utc>,
+) -> Article {
+    let url = entry
+        .links()
+        .iter()
+        .find(|link| link.rel() == "alternate")
+        .or(entry.links().first())
+        .map(|link| link.href())
+        .unwrap_or(entry.id());
+    let author = entry
+        .authors()
+        .first()
+        .or(feed.authors().first())
+        .map(|person| person.name())
+        .unwrap_or(feed.title().as_str());
+    let summary = entry
+        .summary()
+        .map(|summary| summary.as_str())
+        .or(entry.content().and_then(|content| content.value()))
+        .unwrap_or_default();
+    Article::new(
+        media,
+        source_id(feed_url, entry.id()),
+        entry.title().as_str().to_string(),
+        author.to_string(),
+        url.to_string(),
+        summary.to_string(),
+        DatetimeFormatter::atom_to(entry.published().unwrap_or(entry.updated())),
+        crawled_at,
+    )
+}
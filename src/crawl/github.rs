@@ -0,0 +1,165 @@
+use crate::article::{normalize_tags, Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::{ACCEPT, USER_AGENT};
+use reqwest::{self, Client};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    credential_or_env, env_var, next_link, skip_invalid, Crawl, CrawlState, Crawled, Media, Source,
+};
+
+const GITHUB_API_BASE_URL: &str = "https://api.github.com";
+/// starred_atを含めて返す。
+const STAR_MEDIA_TYPE: &str = "application/vnd.github.star+json";
+
+#[derive(Debug, Clone)]
+pub struct GithubCrawler {
+    client: Client,
+    user: String,
+    token: String,
+}
+
+impl GithubCrawler {
+    pub fn new(user: String, token: String) -> Self {
+        let client = reqwest::Client::new();
+        GithubCrawler {
+            client,
+            user,
+            token,
+        }
+    }
+
+    /// starした新しい順にLink headerのnextで辿る。
+    /// stateのlast_item_id(前回見えた最新のstar)に到達したらそれ以降は取得しない。
+    async fn fetch_until(&self, state: CrawlState) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let mut next_url = Some(format!(
+            "{}/users/{}/starred?per_page=100",
+            GITHUB_API_BASE_URL, self.user
+        ));
+        let mut articles = vec![];
+        let mut partial = false;
+        let mut pages = 0;
+        'crawl: while let Some(url) = next_url {
+            let res = self
+                .client
+                .get(url)
+                .header(ACCEPT, STAR_MEDIA_TYPE)
+                // user agentがないと403になる。
+                .header(USER_AGENT, "oisource")
+                .bearer_auth(&self.token)
+                .send()
+                .await?
+                .error_for_status()?;
+            pages += 1;
+            next_url = next_link(res.headers());
+            let stars = res.json::<Vec<Star>>().await?;
+            for article in stars.iter().filter_map(|star| {
+                skip_invalid(&mut partial, star.to_article(self.media(), crawled_at))
+            }) {
+                if Some(&article.source_id) == state.last_item_id.as_ref() {
+                    break 'crawl;
+                }
+                articles.push(article);
+            }
+        }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages,
+            partial,
+        })
+    }
+}
+
+#[async_trait]
+impl Crawl for GithubCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let user = env_var(env_key::GITHUB_USER)?;
+        let token = env_var(env_key::GITHUB_TOKEN)?;
+        Ok(GithubCrawler::new(user, token))
+    }
+
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        let token = credential_or_env(source, env_key::GITHUB_TOKEN)?;
+        Ok(GithubCrawler::new(source.account.clone(), token))
+    }
+
+    fn media(&self) -> Media {
+        Media::Github
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::new(self.media(), "")).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::find(states, self.media(), ""))
+            .await
+    }
+}
+
+/// github starred api response schema.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Star {
+    starred_at: String,
+    repo: Repo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Repo {
+    id: i64,
+    full_name: String,
+    html_url: String,
+    description: Option<String>,
+    language: Option<String>,
+    #[serde(default)]
+    topics: Vec<String>,
+    owner: Owner,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Owner {
+    login: String,
+}
+
+impl Star {
+    fn to_article(&self, media: Media, crawled_at: DateTime<Utc>) -> Result<Article, MyError> {
+        let repo = &self.repo;
+        // description、language、topicsをまとめて検索できるようにする。
+        let summary = [
+            repo.description.clone(),
+            repo.language.clone(),
+            Some(repo.topics.join(", ")),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<String>>()
+        .join(" / ");
+        Ok(Article {
+            tags: normalize_tags(repo.topics.clone()),
+            ..Article::new(
+                media,
+                repo.id.to_string(),
+                repo.full_name.clone(),
+                repo.owner.login.clone(),
+                repo.html_url.clone(),
+                summary,
+                // repoの作成日ではなくstarした日時。
+                DatetimeFormatter::github_to(&self.starred_at)?,
+                crawled_at,
+            )
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use crate::article::{Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{self, Client};
+use serde::{Deserialize, Serialize};
+
+use super::{env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
+
+const HACKER_NEWS_BASE_URL: &str = "https://news.ycombinator.com";
+const HACKER_NEWS_API_BASE_URL: &str = "https://hacker-news.firebaseio.com/v0";
+
+/// favoritesは公開されている。upvoteはloginが必要なので取得しない。
+#[derive(Debug, Clone)]
+pub struct HackerNewsCrawler {
+    client: Client,
+    user_id: String,
+}
+
+impl HackerNewsCrawler {
+    pub fn new(user_id: String) -> Self {
+        let client = reqwest::Client::new();
+        HackerNewsCrawler { client, user_id }
+    }
+
+    /// favoritesのapiはないので、htmlからitemのidを取り出す。
+    /// no item then return [].
+    async fn fetch_favorite_ids(&self, page_num: i32) -> Result<Vec<String>, MyError> {
+        let html = self
+            .client
+            .get(format!("{}/favorites", HACKER_NEWS_BASE_URL))
+            .query(&[("id", self.user_id.clone()), ("p", page_num.to_string())])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(favorite_ids(&html))
+    }
+
+    /// 存在しないitemはnullが返る。
+    async fn fetch_item(&self, id: &str) -> Result<Option<Item>, MyError> {
+        let item = self
+            .client
+            .get(format!("{}/item/{}.json", HACKER_NEWS_API_BASE_URL, id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Option<Item>>()
+            .await?;
+        Ok(item)
+    }
+
+    /// favoriteした新しい順にpageを辿る。
+    /// stateのlast_item_id(前回見えた最新のfavorite)に到達したらそれ以降は取得しない。
+    async fn fetch_until(&self, state: CrawlState) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let mut page_num = 1;
+        let mut articles = vec![];
+        let mut partial = false;
+        'crawl: loop {
+            let ids = self.fetch_favorite_ids(page_num).await?;
+            if ids.is_empty() {
+                break;
+            }
+            for id in ids.iter() {
+                if Some(id) == state.last_item_id.as_ref() {
+                    break 'crawl;
+                }
+                // 消されたitemにはtitleもurlもないので取り込まない。
+                let item = match self.fetch_item(id).await? {
+                    Some(item) if !item.deleted => item,
+                    _ => {
+                        log::warn!("skipped missing or deleted item: {}", id);
+                        partial = true;
+                        continue;
+                    }
+                };
+                articles.extend(skip_invalid(
+                    &mut partial,
+                    item.to_article(self.media(), crawled_at),
+                ));
+            }
+            page_num += 1;
+        }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages: page_num,
+            partial,
+        })
+    }
+}
+
+#[async_trait]
+impl Crawl for HackerNewsCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let user_id = env_var(env_key::HACKER_NEWS_USER_ID)?;
+        Ok(HackerNewsCrawler::new(user_id))
+    }
+
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        Ok(HackerNewsCrawler::new(source.account.clone()))
+    }
+
+    fn media(&self) -> Media {
+        Media::HackerNews
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::new(self.media(), "")).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::find(states, self.media(), ""))
+            .await
+    }
+}
+
+/// <tr class="athing submission" id="12345">
+fn favorite_ids(html: &str) -> Vec<String> {
+    html.split("<tr ")
+        .filter(|tag| tag.split('>').next().unwrap_or_default().contains("athing"))
+        .filter_map(|tag| {
+            let rest = tag.split_once("id=")?.1;
+            let id = rest
+                .trim_start_matches(['"', '\''])
+                .chars()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>();
+            (!id.is_empty()).then_some(id)
+        })
+        .collect()
+}
+
+/// hacker news item api response schema.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Item {
+    id: i64,
+    #[serde(default)]
+    by: String,
+    /// unix time
+    time: i64,
+    #[serde(default)]
+    title: String,
+    /// Ask HNなどはurlがない。
+    url: Option<String>,
+    /// Ask HNなどの本文。html
+    text: Option<String>,
+    score: Option<i32>,
+    /// comment数
+    descendants: Option<i32>,
+    #[serde(default)]
+    deleted: bool,
+}
+
+impl Item {
+    fn to_article(&self, media: Media, crawled_at: DateTime<Utc>) -> Result<Article, MyError> {
+        let discussion_url = format!("{}/item?id={}", HACKER_NEWS_BASE_URL, self.id);
+        let article = Article::new(
+            media,
+            self.id.to_string(),
+            self.title.clone(),
+            self.by.clone(),
+            // 外部リンクがなければ議論のページ。
+            self.url.clone().unwrap_or(discussion_url.clone()),
+            self.text.clone().unwrap_or_default(),
+            DatetimeFormatter::hacker_news_to(self.time)?,
+            crawled_at,
+        );
+        Ok(Article {
+            discussion_url: Some(discussion_url),
+            score: self.score,
+            comment_count: self.descendants,
+            ..article
+        })
+    }
+}
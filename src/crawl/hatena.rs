@@ -0,0 +1,185 @@
+use crate::article::{normalize_tags, Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{self, Client};
+use roxmltree::{Document, Node};
+use serde_json::json;
+
+use super::{env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
+
+const HATENA_BOOKMARK_BASE_URL: &str = "https://b.hatena.ne.jp";
+
+/// bookmark.rssはRSS 1.0(RDF)。
+const RSS_NS: &str = "http://purl.org/rss/1.0/";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+
+#[derive(Debug, Clone)]
+pub struct HatenaCrawler {
+    client: Client,
+    user_id: String,
+}
+
+impl HatenaCrawler {
+    pub fn new(user_id: String) -> Self {
+        let client = reqwest::Client::new();
+        HatenaCrawler { client, user_id }
+    }
+
+    /// no item then return [].
+    async fn fetch_page(
+        &self,
+        page_num: i32,
+        crawled_at: DateTime<Utc>,
+        partial: &mut bool,
+    ) -> Result<Vec<Article>, MyError> {
+        let body = self
+            .client
+            .get(format!(
+                "{}/{}/bookmark.rss",
+                HATENA_BOOKMARK_BASE_URL, self.user_id
+            ))
+            .query(&[("page", page_num)])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let doc = Document::parse(&body).map_err(|err| {
+            MyError::UnprocessableEntity(
+                json!({ "error": format!("invalid bookmark feed: {}", err) }),
+            )
+        })?;
+        Ok(doc
+            .descendants()
+            .filter(|node| node.has_tag_name((RSS_NS, "item")))
+            .filter_map(|item| {
+                skip_invalid(
+                    partial,
+                    Bookmark::from_node(item).to_article(self.media(), crawled_at),
+                )
+            })
+            .collect())
+    }
+
+    /// 新しい順にpageを辿る。
+    /// 初回は履歴を全て、以降はstateのlast_item_id(前回見えた最新のbookmark)まで取得する。
+    async fn fetch_until(&self, state: CrawlState) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let mut page_num = 1;
+        let mut articles = vec![];
+        let mut partial = false;
+        'crawl: loop {
+            let partial_articles = self.fetch_page(page_num, crawled_at, &mut partial).await?;
+            if partial_articles.is_empty() {
+                break;
+            }
+            for article in partial_articles.into_iter() {
+                if Some(&article.source_id) == state.last_item_id.as_ref() {
+                    break 'crawl;
+                }
+                articles.push(article);
+            }
+            page_num += 1;
+        }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages: page_num,
+            partial,
+        })
+    }
+}
+
+#[async_trait]
+impl Crawl for HatenaCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let user_id = env_var(env_key::HATENA_USER_ID)?;
+        Ok(HatenaCrawler::new(user_id))
+    }
+
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        Ok(HatenaCrawler::new(source.account.clone()))
+    }
+
+    fn media(&self) -> Media {
+        Media::Hatena
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::new(self.media(), "")).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::find(states, self.media(), ""))
+            .await
+    }
+}
+
+/// bookmark.rssのitem。
+#[derive(Debug, Clone, PartialEq)]
+struct Bookmark {
+    title: String,
+    link: String,
+    comment: String,
+    creator: String,
+    date: String,
+    tags: Vec<String>,
+}
+
+impl Bookmark {
+    fn from_node(item: Node) -> Self {
+        let text = |ns: &str, name: &str| {
+            item.children()
+                .find(|child| child.has_tag_name((ns, name)))
+                .and_then(|child| child.text())
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        Bookmark {
+            title: text(RSS_NS, "title"),
+            link: text(RSS_NS, "link"),
+            comment: text(RSS_NS, "description"),
+            creator: text(DC_NS, "creator"),
+            date: text(DC_NS, "date"),
+            tags: item
+                .children()
+                .filter(|child| child.has_tag_name((DC_NS, "subject")))
+                .filter_map(|child| child.text())
+                .map(|tag| tag.trim().to_string())
+                .collect(),
+        }
+    }
+
+    fn to_article(&self, media: Media, crawled_at: DateTime<Utc>) -> Result<Article, MyError> {
+        // はてブのコメント欄と同じく[tag]commentの形で残す。
+        let tags = self
+            .tags
+            .iter()
+            .map(|tag| format!("[{}]", tag))
+            .collect::<String>();
+        // 同じurlをほかのuserもbookmarkしうるので、bookmarkしたuserで区別する。
+        let source_id = format!("{}:{}", self.creator, self.link);
+        Ok(Article {
+            tags: normalize_tags(self.tags.clone()),
+            ..Article::new(
+                media,
+                source_id,
+                self.title.clone(),
+                self.creator.clone(),
+                self.link.clone(),
+                format!("{}{}", tags, self.comment),
+                DatetimeFormatter::hatena_to(&self.date)?,
+                crawled_at,
+            )
+        })
+    }
+}
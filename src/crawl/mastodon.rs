@@ -0,0 +1,196 @@
+use crate::article::{normalize_tags, Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{self, Client};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{
+    credential_or_env, env_var, next_link, skip_invalid, Crawl, CrawlState, Crawled, Media, Source,
+};
+
+#[derive(Debug, Clone)]
+pub struct MastodonCrawler {
+    client: Client,
+    /// https://mastodon.social
+    instance_url: String,
+    access_token: String,
+}
+
+impl MastodonCrawler {
+    pub fn new(instance_url: String, access_token: String) -> Self {
+        let client = reqwest::Client::new();
+        MastodonCrawler {
+            client,
+            instance_url: instance_url.trim_end_matches('/').to_string(),
+            access_token,
+        }
+    }
+
+    /// お気に入りの新しい順にLink headerのnext(max_id)で辿る。
+    /// stateのlast_item_id(前回見えた最新のお気に入り)に到達したらそれ以降は取得しない。
+    async fn fetch_until(&self, state: CrawlState) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let mut next_url = Some(format!("{}/api/v1/favourites?limit=40", self.instance_url));
+        let mut articles = vec![];
+        let mut partial = false;
+        let mut pages = 0;
+        'crawl: while let Some(url) = next_url {
+            let res = self
+                .client
+                .get(url)
+                .bearer_auth(&self.access_token)
+                .send()
+                .await?
+                .error_for_status()?;
+            pages += 1;
+            next_url = next_link(res.headers());
+            let statuses = res.json::<Vec<Status>>().await?;
+            for article in statuses.iter().filter_map(|status| {
+                skip_invalid(&mut partial, status.to_article(self.media(), crawled_at))
+            }) {
+                if Some(&article.source_id) == state.last_item_id.as_ref() {
+                    break 'crawl;
+                }
+                articles.push(article);
+            }
+        }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages,
+            partial,
+        })
+    }
+}
+
+#[async_trait]
+impl Crawl for MastodonCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let instance_url = env_var(env_key::MASTODON_INSTANCE_URL)?;
+        let access_token = env_var(env_key::MASTODON_ACCESS_TOKEN)?;
+        Ok(MastodonCrawler::new(instance_url, access_token))
+    }
+
+    /// accountはinstanceのurl
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        let access_token = credential_or_env(source, env_key::MASTODON_ACCESS_TOKEN)?;
+        Ok(MastodonCrawler::new(source.account.clone(), access_token))
+    }
+
+    fn media(&self) -> Media {
+        Media::Mastodon
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(15 * 60)
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::new(self.media(), "")).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::find(states, self.media(), ""))
+            .await
+    }
+}
+
+/// mastodon favourites api response schema.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Status {
+    id: String,
+    /// 投稿元instanceでのid。instanceをまたいで一意。
+    uri: String,
+    url: Option<String>,
+    created_at: String,
+    /// html
+    content: String,
+    account: Account,
+    /// hashtag
+    #[serde(default)]
+    tags: Vec<StatusTag>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct StatusTag {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Account {
+    /// 同じinstanceならusername、ほかのinstanceならusername@domain
+    acct: String,
+    url: String,
+}
+
+impl Status {
+    fn to_article(&self, media: Media, crawled_at: DateTime<Utc>) -> Result<Article, MyError> {
+        let text = strip_html(&self.content);
+        Ok(Article {
+            tags: normalize_tags(self.tags.iter().map(|tag| tag.name.clone())),
+            ..Article::new(
+                media,
+                self.uri.clone(),
+                // titleはないので本文。長さは保存時にそろえる。
+                text.clone(),
+                // ほかのinstanceでも区別できるように@user@domainにする。
+                handle(&self.account),
+                // 投稿元instanceでのurl。boostなどでurlがなければuri。
+                self.url.clone().unwrap_or(self.uri.clone()),
+                // summaryはないので、text.
+                text,
+                DatetimeFormatter::mastodon_to(&self.created_at)?,
+                crawled_at,
+            )
+        })
+    }
+}
+
+/// acctにdomainがなければaccountのurlから補う。
+fn handle(account: &Account) -> String {
+    if account.acct.contains('@') {
+        return format!("@{}", account.acct);
+    }
+    let domain = account
+        .url
+        .split("://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or_default();
+    format!("@{}@{}", account.acct, domain)
+}
+
+/// <p>や<br>は空白にしてtagを取り除く。
+fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
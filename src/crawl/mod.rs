@@ -1,41 +1,460 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
+use async_graphql::Enum;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use diesel::sql_types::Text;
+use reqwest::header::{HeaderMap, LINK};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use strum_macros::Display;
 
-use crate::{article::Article, utils::errors::MyError};
+use crate::{article::Article, canonical, utils::db::DbPool, utils::errors::MyError};
+pub use source::Source;
 
+pub mod bluesky;
+pub mod feed;
+pub mod github;
+pub mod hacker_news;
+pub mod hatena;
+pub mod mastodon;
+pub mod oauth;
 pub mod qiita;
+pub mod reddit;
+pub mod run;
+pub mod scheduler;
+pub mod source;
 pub mod twitter;
 pub mod youtube;
+pub mod zenn;
 
+/// 各sourceのcrawlerが実装する。
+/// Registryに登録すると、mutationやbackground runnerからmediaを指定して呼び出せる。
 #[async_trait]
-trait Crawl {
-    fn media(&self) -> String;
-    async fn fetch(&self) -> Result<Vec<Article>, MyError>;
+pub trait Crawl: Send + Sync {
+    /// 環境変数から設定を読み込んで生成する。
+    fn from_env() -> Result<Self, MyError>
+    where
+        Self: Sized;
+    /// sourcesに登録したaccountで生成する。credentialがなければ環境変数を使う。
+    fn from_source(source: &Source) -> Result<Self, MyError>
+    where
+        Self: Sized;
+    fn media(&self) -> Media;
+    /// schedulerで差分crawlする間隔。環境変数で上書きできる。
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+    /// 全件取得
+    async fn fetch(&self) -> Result<Crawled, MyError>;
+    /// fetchで取得したものが保存済みのうちsourceに残っているものの全てか。
+    /// 最新のitemしか見えないsourceはfalseにして、突き合わせで外されたとみなさないようにする。
+    fn is_complete(&self) -> bool {
+        true
+    }
+    /// 差分取得。statesは保存済みのこのmediaのcrawl state。
+    /// 差分取得に対応していないsourceは全件取得する。
+    async fn fetch_to_update(&self, _states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch().await
+    }
+}
+
+/// crawl結果。statesはarticleと同じtransactionで保存する。
+#[derive(Debug, Clone, Default)]
+pub struct Crawled {
+    pub articles: Vec<Article>,
+    pub states: Vec<CrawlState>,
+    /// sourceへrequestしたpage数。
+    pub pages: i32,
+    /// 設定の一部が使えず、見えるはずのitemを取得していない。外されたとはみなさない。
+    pub partial: bool,
 }
 
-#[derive(Debug, Clone, Display)]
-enum Media {
+/// media、account、feed(youtubeのplaylistなど)ごとのcrawlの進み具合。
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrawlState {
+    pub media: Media,
+    /// sourcesのid。環境変数で設定したaccountはNone。
+    pub account_id: Option<String>,
+    /// feedが一つしかないsourceは空文字。
+    pub feed: String,
+    /// 前回のcrawlで見えた最新のitemのsource id。
+    pub last_item_id: Option<String>,
+    /// 前回のcrawlで最後に取得したpageのtoken。
+    pub next_page_token: Option<String>,
+    pub last_crawled_at: Option<DateTime<Utc>>,
+    /// これまでに取得したitem数。
+    pub item_count: i32,
+    /// 前回のresponseのETag。conditional GETに使う。
+    pub etag: Option<String>,
+    /// 前回のresponseのLast-Modified。conditional GETに使う。
+    pub last_modified: Option<String>,
+}
+
+impl CrawlState {
+    pub fn new(media: Media, feed: &str) -> Self {
+        CrawlState {
+            media,
+            account_id: None,
+            feed: feed.to_string(),
+            last_item_id: None,
+            next_page_token: None,
+            last_crawled_at: None,
+            item_count: 0,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// 保存済みのstateがなければ初期状態を返す。
+    pub fn find(states: &[CrawlState], media: Media, feed: &str) -> Self {
+        states
+            .iter()
+            .find(|state| state.media == media && state.feed == feed)
+            .cloned()
+            .unwrap_or_else(|| CrawlState::new(media, feed))
+    }
+
+    /// 今回のcrawl結果を反映する。新しいitemがなければlast_item_idは据え置き。
+    pub fn advance(
+        &self,
+        last_item_id: Option<String>,
+        next_page_token: Option<String>,
+        crawled_at: DateTime<Utc>,
+        fetched_count: usize,
+    ) -> Self {
+        CrawlState {
+            media: self.media,
+            account_id: self.account_id.clone(),
+            feed: self.feed.clone(),
+            last_item_id: last_item_id.or(self.last_item_id.clone()),
+            next_page_token,
+            last_crawled_at: Some(crawled_at),
+            item_count: self.item_count + fetched_count as i32,
+            etag: self.etag.clone(),
+            last_modified: self.last_modified.clone(),
+        }
+    }
+}
+
+/// DBには小文字で保存する。graphqlではQIITAのような大文字。
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Display,
+    PartialEq,
+    Eq,
+    Hash,
+    Enum,
+    Serialize,
+    Deserialize,
+    AsExpression,
+    FromSqlRow,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[sql_type = "Text"]
+pub enum Media {
     Qiita,
     Youtube,
     Twitter,
+    Zenn,
+    /// RSS/Atom
+    Feed,
+    /// はてなブックマーク
+    Hatena,
+    /// starしたrepository
+    Github,
+    Mastodon,
+    Bluesky,
+    HackerNews,
+    Reddit,
 }
 
 impl FromStr for Media {
     type Err = MyError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let m = match s {
+        match s.to_lowercase().as_str() {
             "qiita" => Ok(Self::Qiita),
             "youtube" => Ok(Self::Youtube),
-
             "twitter" => Ok(Self::Twitter),
-            _ => {
-                return Err(MyError::BadRequest(json!({"error":"error"})));
+            "zenn" => Ok(Self::Zenn),
+            "feed" => Ok(Self::Feed),
+            "hatena" => Ok(Self::Hatena),
+            "github" => Ok(Self::Github),
+            "mastodon" => Ok(Self::Mastodon),
+            "bluesky" => Ok(Self::Bluesky),
+            "hackernews" => Ok(Self::HackerNews),
+            "reddit" => Ok(Self::Reddit),
+            _ => Err(MyError::BadRequest(
+                json!({ "error": format!("unknown media: {}", s) }),
+            )),
+        }
+    }
+}
+
+/// crawlする1つのaccount。
+#[derive(Clone)]
+pub struct Account {
+    /// sourcesのid。環境変数で設定したaccountはNone。
+    pub id: Option<String>,
+    pub crawler: Arc<dyn Crawl>,
+}
+
+impl Account {
+    /// 全件取得
+    pub async fn fetch(&self) -> Result<Crawled, MyError> {
+        let crawled = self.crawler.fetch().await?;
+        Ok(self.stamp(crawled).await)
+    }
+
+    /// このaccountのstateだけ渡して差分取得する。
+    pub async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        let states = states
+            .iter()
+            .filter(|state| state.account_id == self.id)
+            .cloned()
+            .collect::<Vec<CrawlState>>();
+        let crawled = self.crawler.fetch_to_update(&states).await?;
+        Ok(self.stamp(crawled).await)
+    }
+
+    /// 取得したarticleとstateにaccountを記録する。
+    /// 短縮urlは行き先を辿ってからcanonical urlにする。
+    async fn stamp(&self, mut crawled: Crawled) -> Crawled {
+        for article in crawled.articles.iter_mut() {
+            article.account_id = self.id.clone();
+            let url = match canonical::is_shortened(&article.url) {
+                true => canonical::resolve(&article.url).await,
+                false => article.url.clone(),
+            };
+            article.canonical_url = Some(canonical::canonicalize(&url));
+        }
+        for state in crawled.states.iter_mut() {
+            state.account_id = self.id.clone();
+        }
+        crawled
+    }
+}
+
+impl Crawled {
+    pub fn append(&mut self, mut other: Crawled) {
+        self.articles.append(&mut other.articles);
+        self.states.append(&mut other.states);
+        self.pages += other.pages;
+        self.partial |= other.partial;
+    }
+}
+
+/// 全accountを順に全件取得する。
+pub async fn fetch(accounts: &[Account]) -> Result<Crawled, MyError> {
+    let mut crawled = Crawled::default();
+    for account in accounts.iter() {
+        crawled.append(account.fetch().await?);
+    }
+    Ok(crawled)
+}
+
+/// 全accountを順に差分取得する。statesはこのmediaの全accountのcrawl state。
+pub async fn fetch_to_update(
+    accounts: &[Account],
+    states: &[CrawlState],
+) -> Result<Crawled, MyError> {
+    let mut crawled = Crawled::default();
+    for account in accounts.iter() {
+        crawled.append(account.fetch_to_update(states).await?);
+    }
+    Ok(crawled)
+}
+
+/// mediaごとにaccountのcrawlerを保持する。
+/// cloneしても登録したaccountと実行中のmediaは共有される。
+#[derive(Clone, Default)]
+pub struct Registry {
+    accounts: Arc<RwLock<HashMap<Media, Vec<Account>>>>,
+    running: Arc<Mutex<HashSet<Media>>>,
+}
+
+/// dropすると実行中のmediaから外れる。
+pub struct RunningGuard {
+    media: Media,
+    running: Arc<Mutex<HashSet<Media>>>,
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.running.lock().unwrap().remove(&self.media);
+    }
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 環境変数が揃っているsourceを全て登録する。youtubeのoauth tokenはpoolから読む。
+    pub fn from_env(pool: &DbPool) -> Self {
+        let registry = Self::new();
+        registry.register_from_env::<qiita::QiitaCrawler>();
+        registry.register_env_crawler(
+            youtube::YoutubeCrawler::from_env()
+                .and_then(|crawler| crawler.with_oauth_from_env(pool.clone())),
+        );
+        registry.register_from_env::<twitter::TwitterCrawler>();
+        registry.register_from_env::<zenn::ZennCrawler>();
+        registry.register_from_env::<feed::FeedCrawler>();
+        registry.register_from_env::<hatena::HatenaCrawler>();
+        registry.register_from_env::<github::GithubCrawler>();
+        registry.register_from_env::<mastodon::MastodonCrawler>();
+        registry.register_from_env::<bluesky::BlueskyCrawler>();
+        registry.register_from_env::<hacker_news::HackerNewsCrawler>();
+        registry.register_from_env::<reddit::RedditCrawler>();
+        registry
+    }
+
+    /// sourcesに登録されたaccountを追加する。設定が足りないaccountは登録しない。
+    pub fn register_sources(&self, sources: &[Source]) {
+        for source in sources.iter() {
+            if let Err(err) = self.register_source(source) {
+                log::warn!("source {} is not registered: {:?}", source.id, err);
             }
+        }
+    }
+
+    /// 同じidのaccountが登録済みなら上書きする。
+    pub fn register(&self, account: Account) {
+        let mut accounts = self.accounts.write().unwrap();
+        let accounts = accounts.entry(account.crawler.media()).or_default();
+        accounts.retain(|registered| registered.id != account.id);
+        accounts.push(account);
+    }
+
+    pub fn register_source(&self, source: &Source) -> Result<(), MyError> {
+        let crawler: Arc<dyn Crawl> = match source.media {
+            Media::Qiita => Arc::new(qiita::QiitaCrawler::from_source(source)?),
+            Media::Youtube => Arc::new(youtube::YoutubeCrawler::from_source(source)?),
+            Media::Twitter => Arc::new(twitter::TwitterCrawler::from_source(source)?),
+            Media::Zenn => Arc::new(zenn::ZennCrawler::from_source(source)?),
+            Media::Feed => Arc::new(feed::FeedCrawler::from_source(source)?),
+            Media::Hatena => Arc::new(hatena::HatenaCrawler::from_source(source)?),
+            Media::Github => Arc::new(github::GithubCrawler::from_source(source)?),
+            Media::Mastodon => Arc::new(mastodon::MastodonCrawler::from_source(source)?),
+            Media::Bluesky => Arc::new(bluesky::BlueskyCrawler::from_source(source)?),
+            Media::HackerNews => Arc::new(hacker_news::HackerNewsCrawler::from_source(source)?),
+            Media::Reddit => Arc::new(reddit::RedditCrawler::from_source(source)?),
         };
-        return m;
+        self.register(Account {
+            id: Some(source.id.clone()),
+            crawler,
+        });
+        Ok(())
+    }
+
+    pub fn unregister_source(&self, source_id: &str) {
+        for accounts in self.accounts.write().unwrap().values_mut() {
+            accounts.retain(|account| account.id.as_deref() != Some(source_id));
+        }
+    }
+
+    fn register_from_env<C: Crawl + 'static>(&self) {
+        self.register_env_crawler(C::from_env())
     }
+
+    fn register_env_crawler<C: Crawl + 'static>(&self, crawler: Result<C, MyError>) {
+        match crawler {
+            Ok(crawler) => self.register(Account {
+                id: None,
+                crawler: Arc::new(crawler),
+            }),
+            Err(err) => log::warn!("crawler is not registered: {:?}", err),
+        }
+    }
+
+    /// mediaの全account。1つもなければerror。
+    pub fn accounts(&self, media: &Media) -> Result<Vec<Account>, MyError> {
+        self.accounts
+            .read()
+            .unwrap()
+            .get(media)
+            .filter(|accounts| !accounts.is_empty())
+            .cloned()
+            .ok_or_else(|| {
+                MyError::NotFound(
+                    json!({ "error": format!("{} crawler is not registered", media) }),
+                )
+            })
+    }
+
+    pub fn medias(&self) -> Vec<Media> {
+        self.accounts
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, accounts)| !accounts.is_empty())
+            .map(|(media, _)| *media)
+            .collect()
+    }
+
+    /// 同じmediaのcrawlが実行中ならNone。
+    pub fn try_start(&self, media: &Media) -> Option<RunningGuard> {
+        let mut running = self.running.lock().unwrap();
+        if !running.insert(*media) {
+            return None;
+        }
+        Some(RunningGuard {
+            media: *media,
+            running: self.running.clone(),
+        })
+    }
+}
+
+/// crawlerの設定用。未設定ならpanicせずにerrorを返す。
+pub(crate) fn env_var(key: &str) -> Result<String, MyError> {
+    env::var(key)
+        .map_err(|_| MyError::BadRequest(json!({ "error": format!("{} is not set", key) })))
+}
+
+/// sourceのcredential。なければ環境変数。
+pub(crate) fn credential_or_env(source: &Source, key: &str) -> Result<String, MyError> {
+    match &source.credential {
+        Some(credential) => Ok(credential.clone()),
+        None => env_var(key),
+    }
+}
+
+/// 日時などが読めないitemはlogに残して取り込まない。1件のためにcrawl全体を失敗させない。
+/// 取り込まなかったitemを外されたと記録しないように、partialを立てる。
+pub(crate) fn skip_invalid(partial: &mut bool, res: Result<Article, MyError>) -> Option<Article> {
+    match res {
+        Ok(article) => Some(article),
+        Err(err) => {
+            log::warn!("skipped invalid item: {:?}", err);
+            *partial = true;
+            None
+        }
+    }
+}
+
+/// Link headerのrel="next"のurl。github、mastodonのpagination。
+/// Link: <https://api.github.com/...&page=2>; rel="next", <...>; rel="last"
+pub(crate) fn next_link(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, rel) = part.split_once(';')?;
+        if rel.trim() != r#"rel="next""# {
+            return None;
+        }
+        Some(
+            url.trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string(),
+        )
+    })
 }
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::Media;
+use crate::utils::errors::MyError;
+
+/// 認可画面からcallbackまでにかかってよい時間
+const STATE_TTL_MINUTES: i64 = 10;
+/// 期限の少し前に更新する。
+const REFRESH_MARGIN_SECONDS: i64 = 60;
+
+/// mediaごとに1つ保存する。
+#[derive(Debug, Clone, PartialEq)]
+pub struct OAuthToken {
+    pub media: Media,
+    pub access_token: String,
+    /// 初回の認可でのみ返る。更新時に返らなければ前のものを使い続ける。
+    pub refresh_token: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl OAuthToken {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at - Duration::seconds(REFRESH_MARGIN_SECONDS) <= Utc::now()
+    }
+}
+
+/// stateごとのmediaと期限
+type PendingStates = Mutex<HashMap<String, (Media, DateTime<Utc>)>>;
+
+/// 発行したstateと期限。CSRF対策にcallbackで照合する。
+fn pending_states() -> &'static PendingStates {
+    static STATES: OnceLock<PendingStates> = OnceLock::new();
+    STATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 推測できないstateを発行する。
+pub fn issue_state(media: Media) -> String {
+    let state = Uuid::new_v4().to_string();
+    let expires_at = Utc::now() + Duration::minutes(STATE_TTL_MINUTES);
+    let mut states = pending_states().lock().unwrap();
+    states.retain(|_, (_, expires_at)| *expires_at > Utc::now());
+    states.insert(state.clone(), (media, expires_at));
+    state
+}
+
+/// 発行したstateは一度しか使えない。
+pub fn verify_state(media: Media, state: &str) -> Result<(), MyError> {
+    match pending_states().lock().unwrap().remove(state) {
+        Some((issued_for, expires_at)) if issued_for == media && expires_at > Utc::now() => Ok(()),
+        _ => Err(MyError::Unauthorized(
+            json!({"error":"invalid or expired oauth state"}),
+        )),
+    }
+}
@@ -1,17 +1,13 @@
-use crate::article::{Article, DatetimeFormatter};
-use crate::store;
-use crate::store::model::store_rdb;
+use crate::article::{normalize_tags, Article, DatetimeFormatter};
+use crate::constants::env_key;
 use crate::utils::errors::MyError;
-use actix_web::HttpResponse;
 use async_trait::async_trait;
-use chrono::Local;
+use chrono::{DateTime, Utc};
 use reqwest::{self, Client};
 use serde::Deserialize;
 use serde::Serialize;
-use serde_json::json;
-use std::{collections::HashMap, env};
 
-use super::Media;
+use super::{credential_or_env, env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct QiitaArticle {
@@ -20,20 +16,25 @@ pub struct QiitaArticle {
     url: String,
     created_at: String,
     user: QiitaUser,
+    #[serde(default)]
+    tags: Vec<QiitaTag>,
 }
 
 impl QiitaArticle {
-    pub fn to_article(&self, media: String, crawled_at: String) -> Article {
-        Article {
-            id: self.id.clone(),
-            title: self.title.clone(),
-            author: self.user.name.clone(),
-            media,
-            url: self.url.clone(),
-            summary: "".to_string().clone(),
-            created_at: DatetimeFormatter::qiita_to(&self.created_at),
-            crawled_at,
-        }
+    pub fn to_article(&self, media: Media, crawled_at: DateTime<Utc>) -> Result<Article, MyError> {
+        Ok(Article {
+            tags: normalize_tags(self.tags.iter().map(|tag| tag.name.clone())),
+            ..Article::new(
+                media,
+                self.id.clone(),
+                self.title.clone(),
+                self.user.name.clone(),
+                self.url.clone(),
+                String::new(),
+                DatetimeFormatter::qiita_to(&self.created_at)?,
+                crawled_at,
+            )
+        })
     }
 }
 
@@ -42,29 +43,34 @@ pub struct QiitaUser {
     name: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QiitaTag {
+    name: String,
+}
+
 #[derive(Debug, Clone)]
-struct QiitaCrawler {
+pub struct QiitaCrawler {
     client: Client,
     access_token: String,
     user_id: String,
-    crawled_at: String,
 }
 impl QiitaCrawler {
-    fn new(access_token: String, user_id: String) -> Self {
-        let crawled_at = Local::now().naive_local().to_string();
+    pub fn new(access_token: String, user_id: String) -> Self {
         let client = reqwest::Client::new();
         QiitaCrawler {
             client,
             access_token,
             user_id,
-            crawled_at,
         }
     }
-    fn media(&self) -> String {
-        Media::Qiita.to_string()
-    }
     /// no item then return [].
-    async fn fetch(&self, page_num: i32, per_page: i32) -> Result<Vec<Article>, MyError> {
+    async fn fetch_page(
+        &self,
+        page_num: i32,
+        per_page: i32,
+        crawled_at: DateTime<Utc>,
+        partial: &mut bool,
+    ) -> Result<Vec<Article>, MyError> {
         let body = self
             .client
             .get(format!(
@@ -82,72 +88,100 @@ impl QiitaCrawler {
 
         let articles = qiita_articles
             .iter()
-            .map(|qiita_article| qiita_article.to_article(self.media(), self.crawled_at.clone()))
+            .filter_map(|qiita_article| {
+                skip_invalid(partial, qiita_article.to_article(self.media(), crawled_at))
+            })
             .collect::<Vec<Article>>();
         Ok(articles)
     }
 }
 
-pub async fn qiita_crawl() -> Result<Vec<Article>, MyError> {
-    // qiita
-    let access_token = env::var("QIITA_ACCESS_TOKEN").expect("qiita access token is not set");
-    let qiita_user_id = env::var("QIITA_USER_ID").expect("qiita user id is not set");
-    let qiita_qrawler = QiitaCrawler::new(access_token, qiita_user_id);
-    let mut articles = vec![];
-    let mut page_num = 1;
-    let per_page = 20;
-    // fetch all items.
-    loop {
-        let mut partial_articles = qiita_qrawler.fetch(page_num, per_page).await?;
-        if partial_articles.is_empty() {
-            break;
-        }
-        articles.append(&mut partial_articles);
-        page_num += 1;
+#[async_trait]
+impl Crawl for QiitaCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let access_token = env_var(env_key::QIITA_ACCESS_TOKEN)?;
+        let user_id = env_var(env_key::QIITA_USER_ID)?;
+        Ok(QiitaCrawler::new(access_token, user_id))
+    }
+
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        let access_token = credential_or_env(source, env_key::QIITA_ACCESS_TOKEN)?;
+        Ok(QiitaCrawler::new(access_token, source.account.clone()))
     }
-    Ok(articles)
-}
 
-pub async fn crawl_to_update(latest_one: Article) -> Result<Vec<Article>, MyError> {
-    // qiita
-    let access_token = env::var("QIITA_ACCESS_TOKEN").expect("qiita access token is not set");
-    let qiita_user_id = env::var("QIITA_USER_ID").expect("qiita user id is not set");
-    let qiita_qrawler = QiitaCrawler::new(access_token, qiita_user_id);
-    let mut page_num = 1;
-    let per_page = 20;
-    // fetch items.
-    // 20こくらいクロールして、latestと比較して、  一致するまで探す。O(n)だけど大した数じゃないのでOK
-    let mut articles_to_update = vec![];
-    loop {
-        let partial_articles = qiita_qrawler.fetch(page_num, per_page).await?;
-        for article in partial_articles.iter() {
-            if latest_one == *article {
+    fn media(&self) -> Media {
+        Media::Qiita
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let mut articles = vec![];
+        let mut partial = false;
+        let mut page_num = 1;
+        let per_page = 20;
+        // fetch all items.
+        loop {
+            let mut partial_articles = self
+                .fetch_page(page_num, per_page, crawled_at, &mut partial)
+                .await?;
+            if partial_articles.is_empty() {
                 break;
-            } else {
-                articles_to_update.push(article.clone());
             }
+            articles.append(&mut partial_articles);
+            page_num += 1;
         }
-        if partial_articles.is_empty() {
-            break;
-        }
-        page_num += 1;
+        let state = CrawlState::new(self.media(), "").advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages: page_num,
+            partial,
+        })
     }
-    Ok(articles_to_update)
-}
-
-pub async fn latest_one() -> Result<Article, MyError> {
-    // fetch latest item
-    let access_token = env::var("QIITA_ACCESS_TOKEN").expect("qiita access token is not set");
-    let qiita_user_id = env::var("QIITA_USER_ID").expect("qiita user id is not set");
-    let qiita_qrawler = QiitaCrawler::new(access_token, qiita_user_id);
-    let page_num = 1;
-    let per_page = 1;
-    let latest_one_by_crawl = qiita_qrawler
-        .fetch(page_num, per_page)
-        .await?
-        .first()
-        .expect("must be one")
-        .clone();
 
-    Ok(latest_one_by_crawl)
+    /// 前回見えた最新のstockのidに一致するまで新しい順に取得する。
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let state = CrawlState::find(states, self.media(), "");
+        let mut page_num = 1;
+        let per_page = 20;
+        // fetch items.
+        // 20こくらいクロールして、latestと比較して、  一致するまで探す。O(n)だけど大した数じゃないのでOK
+        let mut articles_to_update = vec![];
+        let mut partial = false;
+        'crawl: loop {
+            let partial_articles = self
+                .fetch_page(page_num, per_page, crawled_at, &mut partial)
+                .await?;
+            if partial_articles.is_empty() {
+                break;
+            }
+            for article in partial_articles.into_iter() {
+                if Some(&article.source_id) == state.last_item_id.as_ref() {
+                    break 'crawl;
+                }
+                articles_to_update.push(article);
+            }
+            page_num += 1;
+        }
+        let state = state.advance(
+            articles_to_update
+                .first()
+                .map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles_to_update.len(),
+        );
+        Ok(Crawled {
+            articles: articles_to_update,
+            states: vec![state],
+            pages: page_num,
+            partial,
+        })
+    }
 }
@@ -0,0 +1,248 @@
+use crate::article::{Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::header::USER_AGENT;
+use reqwest::{self, Client};
+use serde::{Deserialize, Serialize};
+
+use super::{credential_or_env, env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
+
+const REDDIT_BASE_URL: &str = "https://www.reddit.com";
+const REDDIT_OAUTH_BASE_URL: &str = "https://oauth.reddit.com";
+/// redditはuser agentが一般的なものだと制限される。
+const REDDIT_USER_AGENT: &str = "oisource/0.1";
+
+/// script appのclient idとsecret、userのpasswordでtokenを取得する。
+#[derive(Debug, Clone)]
+pub struct RedditCrawler {
+    client: Client,
+    client_id: String,
+    client_secret: String,
+    username: String,
+    password: String,
+}
+
+impl RedditCrawler {
+    pub fn new(
+        client_id: String,
+        client_secret: String,
+        username: String,
+        password: String,
+    ) -> Self {
+        let client = reqwest::Client::new();
+        RedditCrawler {
+            client,
+            client_id,
+            client_secret,
+            username,
+            password,
+        }
+    }
+
+    async fn fetch_access_token(&self) -> Result<String, MyError> {
+        let token = self
+            .client
+            .post(format!("{}/api/v1/access_token", REDDIT_BASE_URL))
+            .header(USER_AGENT, REDDIT_USER_AGENT)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[
+                ("grant_type", "password"),
+                ("username", &self.username),
+                ("password", &self.password),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<AccessToken>()
+            .await?;
+        Ok(token.access_token)
+    }
+
+    /// saveした新しい順にafterで辿る。
+    /// stateのlast_item_id(前回見えた最新のsave)に到達したらそれ以降は取得しない。
+    async fn fetch_until(&self, state: CrawlState) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let access_token = self.fetch_access_token().await?;
+        let mut after: Option<String> = None;
+        let mut articles = vec![];
+        let mut partial = false;
+        let mut pages = 0;
+        'crawl: loop {
+            let mut query_params =
+                vec![("limit", "100".to_string()), ("raw_json", "1".to_string())];
+            if let Some(after) = after {
+                query_params.push(("after", after));
+            }
+            let listing = self
+                .client
+                .get(format!(
+                    "{}/user/{}/saved",
+                    REDDIT_OAUTH_BASE_URL, self.username
+                ))
+                .query(&query_params)
+                .header(USER_AGENT, REDDIT_USER_AGENT)
+                .bearer_auth(&access_token)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Listing>()
+                .await?;
+            pages += 1;
+            let saved = listing.data.children.iter().filter_map(|child| {
+                skip_invalid(
+                    &mut partial,
+                    child.data.to_article(self.media(), crawled_at),
+                )
+            });
+            for article in saved {
+                if Some(&article.source_id) == state.last_item_id.as_ref() {
+                    break 'crawl;
+                }
+                articles.push(article);
+            }
+            match listing.data.after {
+                Some(next) => after = Some(next),
+                None => break,
+            }
+        }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages,
+            partial,
+        })
+    }
+}
+
+#[async_trait]
+impl Crawl for RedditCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let client_id = env_var(env_key::REDDIT_CLIENT_ID)?;
+        let client_secret = env_var(env_key::REDDIT_CLIENT_SECRET)?;
+        let username = env_var(env_key::REDDIT_USERNAME)?;
+        let password = env_var(env_key::REDDIT_PASSWORD)?;
+        Ok(RedditCrawler::new(
+            client_id,
+            client_secret,
+            username,
+            password,
+        ))
+    }
+
+    /// accountはusername、credentialはpassword。script appは共通のものを使う。
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        let client_id = env_var(env_key::REDDIT_CLIENT_ID)?;
+        let client_secret = env_var(env_key::REDDIT_CLIENT_SECRET)?;
+        let password = credential_or_env(source, env_key::REDDIT_PASSWORD)?;
+        Ok(RedditCrawler::new(
+            client_id,
+            client_secret,
+            source.account.clone(),
+            password,
+        ))
+    }
+
+    fn media(&self) -> Media {
+        Media::Reddit
+    }
+
+    /// savedは新しい1000件ほどまでしか辿れない。
+    fn is_complete(&self) -> bool {
+        false
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::new(self.media(), "")).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::find(states, self.media(), ""))
+            .await
+    }
+}
+
+/// reddit api response schema.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct AccessToken {
+    access_token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Listing {
+    data: ListingData,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ListingData {
+    after: Option<String>,
+    children: Vec<Thing>,
+}
+
+/// kindはt3(post)かt1(comment)。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Thing {
+    kind: String,
+    data: Saved,
+}
+
+/// postとcommentで共通して使う項目。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Saved {
+    /// t3_xxxのようなfullname。kindをまたいで一意。
+    name: String,
+    author: String,
+    /// /r/rust/comments/xxx/...
+    permalink: String,
+    score: Option<i32>,
+    created_utc: f64,
+    /// post
+    title: Option<String>,
+    url: Option<String>,
+    selftext: Option<String>,
+    num_comments: Option<i32>,
+    /// comment
+    body: Option<String>,
+    link_title: Option<String>,
+    link_url: Option<String>,
+}
+
+impl Saved {
+    fn to_article(&self, media: Media, crawled_at: DateTime<Utc>) -> Result<Article, MyError> {
+        let discussion_url = format!("{}{}", REDDIT_BASE_URL, self.permalink);
+        let article = Article::new(
+            media,
+            self.name.clone(),
+            self.title
+                .clone()
+                .or(self.link_title.clone())
+                .unwrap_or_default(),
+            self.author.clone(),
+            // self postのurlはpermalinkと同じ。
+            self.url
+                .clone()
+                .or(self.link_url.clone())
+                .unwrap_or(discussion_url.clone()),
+            self.selftext
+                .clone()
+                .or(self.body.clone())
+                .unwrap_or_default(),
+            DatetimeFormatter::reddit_to(self.created_utc)?,
+            crawled_at,
+        );
+        Ok(Article {
+            discussion_url: Some(discussion_url),
+            score: self.score,
+            comment_count: self.num_comments,
+            ..article
+        })
+    }
+}
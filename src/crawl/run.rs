@@ -0,0 +1,219 @@
+use async_graphql::{Enum, SimpleObject};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{Account, Media, Registry, RunningGuard};
+use crate::article::Article;
+use crate::store;
+use crate::utils::db::DbPool;
+use crate::utils::errors::MyError;
+
+/// 1回のcrawlの実行記録。finished_atがNoneなら実行中。
+#[derive(Debug, Clone, PartialEq, SimpleObject)]
+pub struct CrawlRun {
+    pub id: String,
+    pub media: Media,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// 成功したらNone
+    pub error: Option<String>,
+    pub pages_fetched: i32,
+    pub articles_inserted: i32,
+    pub articles_updated: i32,
+    /// 保存済みで、sourceの内容も変わっていない。
+    pub articles_unchanged: i32,
+    /// sourceで外されたとして記録した件数。RECONCILEのときのみ。
+    pub articles_removed: i32,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrawlMode {
+    /// 前回のcrawl以降に追加されたものを取得する。
+    #[default]
+    Update,
+    /// 全件取得して保存する。外されたものは記録しない。
+    Full,
+    /// 全件取得して、保存済みのうちsourceにないものをremovedAtで記録する。
+    Reconcile,
+}
+
+impl CrawlRun {
+    pub fn start(media: Media) -> Self {
+        CrawlRun {
+            id: Uuid::new_v4().to_string(),
+            media,
+            started_at: Utc::now(),
+            finished_at: None,
+            error: None,
+            pages_fetched: 0,
+            articles_inserted: 0,
+            articles_updated: 0,
+            articles_unchanged: 0,
+            articles_removed: 0,
+        }
+    }
+
+    fn finish(&mut self, error: Option<String>) {
+        self.finished_at = Some(Utc::now());
+        self.error = error;
+    }
+}
+
+/// 実行記録を作成済みで、まだcrawlしていないjob。
+/// dropするまで同じmediaのcrawlは開始できない。
+pub struct Job {
+    run: CrawlRun,
+    mode: CrawlMode,
+    accounts: Vec<Account>,
+    pool: DbPool,
+    _guard: RunningGuard,
+}
+
+impl Job {
+    /// 同じmediaのcrawlが実行中ならerror。
+    pub fn prepare(
+        registry: &Registry,
+        pool: &DbPool,
+        media: Media,
+        mode: CrawlMode,
+    ) -> Result<Job, MyError> {
+        let guard = registry.try_start(&media).ok_or_else(|| {
+            MyError::UnprocessableEntity(json!({ "error": format!("{} crawl is running", media) }))
+        })?;
+        let accounts = registry.accounts(&media)?;
+        let run = CrawlRun::start(media);
+        let conn = pool.get()?;
+        store::crawl_run::insert(&conn, &run)?;
+        Ok(Job {
+            run,
+            mode,
+            accounts,
+            pool: pool.clone(),
+            _guard: guard,
+        })
+    }
+
+    pub fn run(&self) -> &CrawlRun {
+        &self.run
+    }
+
+    /// mediaの全accountをcrawlして保存し、結果をcrawl_runsに記録する。
+    pub async fn execute(self) -> Result<CrawlRun, MyError> {
+        let (run, _) = self.execute_collecting().await?;
+        Ok(run)
+    }
+
+    /// executeと同じで、取得したarticleも返す。crawlに失敗したらarticleは空。
+    pub async fn execute_collecting(mut self) -> Result<(CrawlRun, Vec<Article>), MyError> {
+        let res = match self.mode {
+            CrawlMode::Update => self.crawl_and_store(false).await,
+            CrawlMode::Full => self.crawl_and_store(true).await,
+            CrawlMode::Reconcile => self.reconcile_and_store().await,
+        };
+        let (articles, error) = match res {
+            Ok(articles) => (articles, None),
+            Err(err) => (vec![], Some(format!("{:?}", err))),
+        };
+        self.run.finish(error);
+        let conn = self.pool.get()?;
+        store::crawl_run::update(&conn, &self.run)?;
+        Ok((self.run, articles))
+    }
+
+    async fn crawl_and_store(&mut self, full: bool) -> Result<Vec<Article>, MyError> {
+        let conn = self.pool.get()?;
+        let crawled = if full {
+            super::fetch(&self.accounts).await?
+        } else {
+            let states = store::crawl_state::load(&conn, &self.run.media)?;
+            super::fetch_to_update(&self.accounts, &states).await?
+        };
+        self.run.pages_fetched = crawled.pages;
+        store::crawl_run::update(&conn, &self.run)?;
+        let count = store::model::store_rdb(&conn, &crawled.articles, &crawled.states)?;
+        self.run.articles_inserted = count.inserted as i32;
+        self.run.articles_updated = count.updated as i32;
+        self.run.articles_unchanged = count.unchanged as i32;
+        Ok(crawled.articles)
+    }
+
+    /// accountごとに全件取得して保存し、見えなくなったものを外されたとして記録する。
+    async fn reconcile_and_store(&mut self) -> Result<Vec<Article>, MyError> {
+        let conn = self.pool.get()?;
+        let removed_at = Utc::now();
+        let mut articles = vec![];
+        for account in self.accounts.iter() {
+            let mut crawled = account.fetch().await?;
+            self.run.pages_fetched += crawled.pages;
+            store::crawl_run::update(&conn, &self.run)?;
+            let count = store::model::store_rdb(&conn, &crawled.articles, &crawled.states)?;
+            self.run.articles_inserted += count.inserted as i32;
+            self.run.articles_updated += count.updated as i32;
+            self.run.articles_unchanged += count.unchanged as i32;
+            let source_ids = crawled
+                .articles
+                .iter()
+                .map(|article| article.source_id.clone())
+                .collect::<Vec<String>>();
+            articles.append(&mut crawled.articles);
+            // 何も見えないのはsource側の不調かもしれないので、外さない。
+            if !account.crawler.is_complete() || crawled.partial || source_ids.is_empty() {
+                continue;
+            }
+            let removed = store::model::mark_removed(
+                &conn,
+                self.run.media,
+                account.id.as_deref(),
+                &source_ids,
+                removed_at,
+            )?;
+            self.run.articles_removed += removed as i32;
+        }
+        Ok(articles)
+    }
+}
+
+/// 差分crawlが終わるまで待つ。
+pub async fn run_to_update(
+    registry: &Registry,
+    pool: &DbPool,
+    media: Media,
+) -> Result<CrawlRun, MyError> {
+    Job::prepare(registry, pool, media, CrawlMode::Update)?
+        .execute()
+        .await
+}
+
+/// crawlが終わるまで待ち、取得したarticleを返す。失敗は実行記録に残した上でerrorにする。
+pub async fn run_collecting(
+    registry: &Registry,
+    pool: &DbPool,
+    media: Media,
+    mode: CrawlMode,
+) -> Result<Vec<Article>, MyError> {
+    let (run, articles) = Job::prepare(registry, pool, media, mode)?
+        .execute_collecting()
+        .await?;
+    match run.error {
+        Some(error) => Err(MyError::UnprocessableEntity(json!({ "error": error }))),
+        None => Ok(articles),
+    }
+}
+
+/// crawlをbackgroundで開始し、実行記録をすぐに返す。
+pub fn start(
+    registry: &Registry,
+    pool: &DbPool,
+    media: Media,
+    mode: CrawlMode,
+) -> Result<CrawlRun, MyError> {
+    let job = Job::prepare(registry, pool, media, mode)?;
+    let run = job.run().clone();
+    tokio::spawn(async move {
+        if let Err(err) = job.execute().await {
+            log::warn!("failed to record crawl run: {:?}", err);
+        }
+    });
+    Ok(run)
+}
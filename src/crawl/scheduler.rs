@@ -0,0 +1,57 @@
+use std::env;
+use std::time::Duration;
+
+use rand::Rng;
+
+use super::{run, Media, Registry};
+use crate::utils::db::DbPool;
+
+/// 登録済みの各mediaを、それぞれの間隔で差分crawlする。後から追加したmediaは再起動するまでscheduleしない。
+/// 間隔は`CRAWL_INTERVAL_{MEDIA}`(分)で上書きでき、0ならそのsourceはscheduleしない。
+pub fn spawn(registry: Registry, pool: DbPool) {
+    for media in registry.medias() {
+        let interval = match interval(&registry, &media) {
+            Some(interval) => interval,
+            None => {
+                log::info!("{} crawl is not scheduled", media);
+                continue;
+            }
+        };
+        log::info!("{} crawl is scheduled every {:?}", media, interval);
+        let registry = registry.clone();
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval + jitter(interval)).await;
+                match run::run_to_update(&registry, &pool, media).await {
+                    Ok(run) => match run.error {
+                        Some(err) => log::warn!("{} crawl {} failed: {}", media, run.id, err),
+                        None => log::info!("{} crawl {} finished", media, run.id),
+                    },
+                    // 前回のcrawlが実行中ならskipする。
+                    Err(err) => log::warn!("{} crawl is skipped: {:?}", media, err),
+                }
+            }
+        });
+    }
+}
+
+fn interval(registry: &Registry, media: &Media) -> Option<Duration> {
+    let key = format!("CRAWL_INTERVAL_{}", media.to_string().to_uppercase());
+    let interval = match env::var(&key).ok().and_then(|v| v.parse::<u64>().ok()) {
+        Some(minutes) => Duration::from_secs(minutes * 60),
+        None => registry
+            .accounts(media)
+            .ok()?
+            .first()?
+            .crawler
+            .default_interval(),
+    };
+    Some(interval).filter(|interval| !interval.is_zero())
+}
+
+/// 同時刻にsourceへのrequestが集中しないように、間隔の1割までずらす。
+fn jitter(interval: Duration) -> Duration {
+    let max = interval.as_millis() as u64 / 10;
+    Duration::from_millis(rand::thread_rng().gen_range(0..=max))
+}
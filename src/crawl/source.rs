@@ -0,0 +1,33 @@
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::Media;
+
+/// 1つのmediaに複数登録できるaccount。
+#[derive(Debug, Clone, PartialEq, SimpleObject)]
+pub struct Source {
+    pub id: String,
+    pub media: Media,
+    /// 表示用の名前
+    pub label: String,
+    /// user idやhandle、feedのurlなど、mediaごとのaccountの指定。
+    pub account: String,
+    /// tokenなど。Noneなら環境変数のものを使う。
+    #[graphql(skip)]
+    pub credential: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Source {
+    pub fn new(media: Media, label: String, account: String, credential: Option<String>) -> Self {
+        Source {
+            id: Uuid::new_v4().to_string(),
+            media,
+            label,
+            account,
+            credential,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -1,62 +1,142 @@
-use crate::article::{Article, DatetimeFormatter};
-use crate::store;
-use crate::store::model::store_rdb;
+use crate::article::{Article, ArticleMetadata, Attachment, DatetimeFormatter, QuotedPost};
+use crate::constants::env_key;
 use crate::utils::errors::MyError;
-use actix_web::HttpResponse;
 use async_trait::async_trait;
-use chrono::Local;
+use chrono::{DateTime, Utc};
 use reqwest::{self, Client};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use std::{collections::HashMap, env};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use super::{credential_or_env, env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
 
 const TWITTER_API_BASE_URL: &str = "https://api.twitter.com/2/";
 
-pub async fn twitter_crawl() -> Result<Vec<Article>, MyError> {
-    const media: &str = "twitter";
-    let crawled_at = Local::now().naive_local().to_string();
-    let twitter_user_id = env::var("TWITTER_USER_ID").expect("twitter user id is not set");
-    let bearer_token = env::var("TWITTER_BEARER_TOKEN").expect("twitter bearer token is not set");
-
-    let client = reqwest::Client::new();
-    let mut next_page_token: Option<String> = None;
-    let mut articles = vec![];
-    loop {
-        let favorite_res =
-            fetch_twitter_favorite(&client, &twitter_user_id, &bearer_token, next_page_token)
-                .await?;
-        match favorite_res.data {
-            Some(data) => {
-                // usersから該当のuserをauthor_idで検索する
-                let mut part_of_articles = data
-                    .into_iter()
-                    .map(|tweet| {
-                        tweet.to_article(
-                            favorite_res
-                                .includes
-                                .as_ref()
-                                .unwrap()
-                                .users
-                                .iter()
-                                .find(|&user| user.id == tweet.author_id)
-                                .unwrap()
-                                .username
-                                .clone(),
-                            media.to_owned(),
-                            crawled_at.clone(),
-                        )
-                    })
-                    .collect::<Vec<Article>>();
-                articles.append(&mut part_of_articles);
-            }
-            None => break,
+#[derive(Debug, Clone)]
+pub struct TwitterCrawler {
+    client: Client,
+    /// 数値のuser idかusername
+    user: String,
+    bearer_token: String,
+    /// usernameから解決したuser id
+    user_id: OnceCell<String>,
+}
+
+impl TwitterCrawler {
+    pub fn new(user: String, bearer_token: String) -> Self {
+        let client = reqwest::Client::new();
+        TwitterCrawler {
+            client,
+            user: user.trim_start_matches('@').to_string(),
+            bearer_token,
+            user_id: OnceCell::new(),
         }
-        match favorite_res.meta.next_token {
-            Some(t) => next_page_token = Some(t),
-            None => break,
+    }
+
+    /// liked_tweetsには数値のidが必要。usernameなら初回のみ解決する。
+    async fn user_id(&self) -> Result<&String, MyError> {
+        self.user_id
+            .get_or_try_init(|| async {
+                if self.user.chars().all(|c| c.is_ascii_digit()) {
+                    return Ok(self.user.clone());
+                }
+                let user_res = self
+                    .client
+                    .get(format!(
+                        "{}users/by/username/{}",
+                        TWITTER_API_BASE_URL, self.user
+                    ))
+                    .bearer_auth(&self.bearer_token)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<UserRes>()
+                    .await?;
+                Ok(user_res.data.id)
+            })
+            .await
+    }
+
+    /// いいねの新しい順にpagination_tokenで辿る。
+    /// stateのlast_item_id(前回見えた最新のいいね)に到達したらそれ以降は取得しない。
+    async fn fetch_until(&self, state: CrawlState) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let user_id = self.user_id().await?;
+        let mut next_page_token: Option<String> = None;
+        let mut last_page_token;
+        let mut articles = vec![];
+        let mut partial = false;
+        let mut pages = 0;
+        'crawl: loop {
+            last_page_token = next_page_token.clone();
+            let favorite_res =
+                fetch_twitter_favorite(&self.client, user_id, &self.bearer_token, next_page_token)
+                    .await?;
+            pages += 1;
+            let includes = favorite_res.includes.unwrap_or_default();
+            match favorite_res.data {
+                Some(data) => {
+                    for tweet in data.into_iter() {
+                        if Some(&tweet.id) == state.last_item_id.as_ref() {
+                            break 'crawl;
+                        }
+                        articles.extend(skip_invalid(
+                            &mut partial,
+                            tweet.to_article(&includes, self.media(), crawled_at),
+                        ));
+                    }
+                }
+                None => break,
+            }
+            match favorite_res.meta.next_token {
+                Some(t) => next_page_token = Some(t),
+                None => break,
+            }
         }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            last_page_token,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages,
+            partial,
+        })
+    }
+}
+
+#[async_trait]
+impl Crawl for TwitterCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let user_id = env_var(env_key::TWITTER_USER_ID)?;
+        let bearer_token = env_var(env_key::TWITTER_BEARER_TOKEN)?;
+        Ok(TwitterCrawler::new(user_id, bearer_token))
+    }
+
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        let bearer_token = credential_or_env(source, env_key::TWITTER_BEARER_TOKEN)?;
+        Ok(TwitterCrawler::new(source.account.clone(), bearer_token))
+    }
+
+    fn media(&self) -> Media {
+        Media::Twitter
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(15 * 60)
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::new(self.media(), "")).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_until(CrawlState::find(states, self.media(), ""))
+            .await
     }
-    Ok(articles)
 }
 
 /// itemがなくてもnext_tokenが帰ってくる。
@@ -68,9 +148,21 @@ async fn fetch_twitter_favorite(
     page_token: Option<String>,
 ) -> Result<FavoriteRes, MyError> {
     // 空文字 pagination_tokenは怒られる.
+    // 引用されたtweetと添付mediaもincludesで返してもらう。
     let mut query_params = vec![
-        ("expansions", "author_id".to_string()),
-        ("tweet.fields", "created_at".to_string()),
+        (
+            "expansions",
+            "author_id,referenced_tweets.id,referenced_tweets.id.author_id,attachments.media_keys"
+                .to_string(),
+        ),
+        (
+            "tweet.fields",
+            "created_at,entities,referenced_tweets,attachments".to_string(),
+        ),
+        (
+            "media.fields",
+            "type,url,preview_image_url,alt_text".to_string(),
+        ),
     ];
     if let Some(tk) = page_token {
         query_params.push(("pagination_token", tk))
@@ -87,7 +179,7 @@ async fn fetch_twitter_favorite(
         .await?
         .text()
         .await?;
-    println!("{}", raw_res);
+    log::debug!("{}", raw_res);
     return Ok(serde_json::from_str::<FavoriteRes>(&raw_res)?);
 }
 
@@ -100,9 +192,31 @@ struct FavoriteRes {
     includes: Option<Expansion>,
 }
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct UserRes {
+    data: TweetUser,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 struct Expansion {
+    #[serde(default)]
     users: Vec<TweetUser>,
+    /// 引用されたtweet
+    #[serde(default)]
+    tweets: Vec<Tweet>,
+    #[serde(default)]
+    media: Vec<TweetMedia>,
 }
+
+impl Expansion {
+    fn username(&self, user_id: &str) -> String {
+        self.users
+            .iter()
+            .find(|user| user.id == user_id)
+            .map(|user| user.username.clone())
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 struct TweetUser {
     id: String,
@@ -122,24 +236,161 @@ struct Tweet {
     author_id: String,
     created_at: String,
     text: String,
+    entities: Option<TweetEntities>,
+    referenced_tweets: Option<Vec<ReferencedTweet>>,
+    attachments: Option<TweetAttachments>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct TweetEntities {
+    #[serde(default)]
+    urls: Vec<UrlEntity>,
+}
+
+/// t.coの展開先。titleとdescriptionはリンク先のページから取得されたもの。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct UrlEntity {
+    /// t.co
+    url: String,
+    expanded_url: String,
+    /// redirectを辿った先
+    unwound_url: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+}
+
+impl UrlEntity {
+    /// 引用tweetや添付mediaのurlはtwitter自身を指す。
+    fn is_external(&self) -> bool {
+        !["https://twitter.com/", "https://x.com/"]
+            .iter()
+            .any(|prefix| self.expanded_url.starts_with(prefix))
+    }
+
+    fn target_url(&self) -> String {
+        self.unwound_url
+            .clone()
+            .unwrap_or(self.expanded_url.clone())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ReferencedTweet {
+    /// quoted、replied_to、retweeted
+    #[serde(rename = "type")]
+    kind: String,
+    id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct TweetAttachments {
+    #[serde(default)]
+    media_keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct TweetMedia {
+    media_key: String,
+    #[serde(rename = "type")]
+    kind: String,
+    url: Option<String>,
+    preview_image_url: Option<String>,
+    alt_text: Option<String>,
 }
 
 impl Tweet {
-    fn to_article(&self, author: String, media: String, crawled_at: String) -> Article {
-        Article {
-            id: self.id.clone(),
-            title: self.text.clone(),
-            author: author.clone(),
+    fn url(&self, author: &str) -> String {
+        format!("https://twitter.com/{}/status/{}", author, self.id)
+    }
+
+    fn urls(&self) -> Vec<UrlEntity> {
+        self.entities
+            .as_ref()
+            .map(|entities| entities.urls.clone())
+            .unwrap_or_default()
+    }
+
+    /// t.coを展開先に置き換えたtext
+    fn expanded_text(&self) -> String {
+        self.urls().iter().fold(self.text.clone(), |text, entity| {
+            text.replace(&entity.url, &entity.target_url())
+        })
+    }
+
+    /// 外部リンクがあればリンク先を記事として、tweetはdiscussion_urlに残す。
+    fn to_article(
+        &self,
+        includes: &Expansion,
+        media: Media,
+        crawled_at: DateTime<Utc>,
+    ) -> Result<Article, MyError> {
+        let author = includes.username(&self.author_id);
+        let tweet_url = self.url(&author);
+        let text = self.expanded_text();
+        let link = self.urls().into_iter().find(|entity| entity.is_external());
+        let article = Article::new(
             media,
-            url: format!("https://twitter.com/{}/status/{}", author, self.id),
-            // summaryはないので、text.
-            summary: self.text.clone(),
-            created_at: DatetimeFormatter::twitter_to(&self.created_at),
+            self.id.clone(),
+            link.as_ref()
+                .and_then(|link| link.title.clone())
+                .unwrap_or(text.clone()),
+            author.clone(),
+            link.as_ref()
+                .map(|link| link.target_url())
+                .unwrap_or(tweet_url.clone()),
+            // リンク先の説明がなければtext.
+            link.as_ref()
+                .and_then(|link| link.description.clone())
+                .unwrap_or(text),
+            DatetimeFormatter::twitter_to(&self.created_at)?,
             crawled_at,
-        }
+        );
+        Ok(Article {
+            discussion_url: link.as_ref().map(|_| tweet_url),
+            metadata: self.metadata(includes),
+            ..article
+        })
     }
-}
 
-struct TweetCrawler {
-    access_token: String,
+    fn metadata(&self, includes: &Expansion) -> Option<ArticleMetadata> {
+        let quoted = self
+            .referenced_tweets
+            .iter()
+            .flatten()
+            .find(|referenced| referenced.kind == "quoted")
+            .and_then(|referenced| {
+                includes
+                    .tweets
+                    .iter()
+                    .find(|tweet| tweet.id == referenced.id)
+            })
+            .map(|tweet| {
+                let author = includes.username(&tweet.author_id);
+                QuotedPost {
+                    id: tweet.id.clone(),
+                    url: tweet.url(&author),
+                    author,
+                    text: tweet.expanded_text(),
+                }
+            });
+        let attachments = self
+            .attachments
+            .iter()
+            .flat_map(|attachments| attachments.media_keys.iter())
+            .filter_map(|key| includes.media.iter().find(|media| &media.media_key == key))
+            .map(|media| Attachment {
+                kind: media.kind.clone(),
+                url: media.url.clone(),
+                preview_url: media.preview_image_url.clone(),
+                alt_text: media.alt_text.clone(),
+            })
+            .collect::<Vec<Attachment>>();
+        if quoted.is_none() && attachments.is_empty() {
+            return None;
+        }
+        Some(ArticleMetadata {
+            quoted,
+            attachments,
+        })
+    }
 }
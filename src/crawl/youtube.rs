@@ -1,85 +1,297 @@
-use crate::article::{Article, DatetimeFormatter};
+use crate::article::{normalize_tags, Article, DatetimeFormatter};
+use crate::constants::env_key;
 use crate::store;
-use crate::store::model::store_rdb;
+use crate::utils::db::DbPool;
 use crate::utils::errors::MyError;
-use actix_web::HttpResponse;
 use async_trait::async_trait;
-use chrono::Local;
-use reqwest::{self, Client};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::{self, Client, RequestBuilder, Url};
 use serde_json::json;
-use std::{collections::HashMap, env};
+use std::collections::HashMap;
+use std::fmt;
+
+use super::oauth::{self, OAuthToken};
+use super::{credential_or_env, env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
 
 const YOUTUBE_API_BASE_URL: &str = "https://www.googleapis.com/youtube/";
+const GOOGLE_AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const YOUTUBE_READONLY_SCOPE: &str = "https://www.googleapis.com/auth/youtube.readonly";
+/// 高く評価した動画。mine=trueのplaylist一覧には含まれない。
+const LIKED_VIDEOS_PLAYLIST_ID: &str = "LL";
+const VIDEOS_PER_REQUEST: usize = 50;
 
-pub async fn youtube_crawl_unauthorized() -> Result<Vec<Article>, MyError> {
-    let crawled_at = Local::now().naive_local().to_string();
-    let api_key = env::var("YOUTUBE_API_KEY").expect("youtube api key is not set");
-    let channel_id = env::var("YOUTUBE_CHANNEL_ID").expect("youtube channel id is not set");
-    let client = reqwest::Client::new();
-    let mut playlists = vec![];
-    let mut next_page_token_for_playlists = "".to_string();
-    // playlist一覧を取得
-    // nextTokenがなくなるまで全取得
-    loop {
-        let mut playlistres = fetch_youtube_playlists(
-            &client,
-            &api_key,
-            &channel_id,
-            &next_page_token_for_playlists,
-        )
-        .await?;
-        playlists.append(&mut playlistres.items);
-        match playlistres.next_page_token {
-            Some(t) => next_page_token_for_playlists = t,
-            None => break,
+#[derive(Debug, Clone)]
+pub struct YoutubeCrawler {
+    client: Client,
+    api_key: String,
+    channel_id: String,
+    /// 認可済みなら非公開のplaylistも取得する。
+    oauth: Option<YoutubeOAuth>,
+}
+
+/// api keyは公開playlistのみ、bearerは認可したuserの非公開playlistも見られる。
+#[derive(Debug, Clone)]
+enum Credential {
+    ApiKey(String),
+    Bearer(String),
+}
+
+impl Credential {
+    fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+        match self {
+            Credential::ApiKey(api_key) => req.query(&[("key", api_key)]),
+            Credential::Bearer(access_token) => req.bearer_auth(access_token),
         }
     }
-    // 各playlist一覧からitemを取得
-    // nextTokenがなくなるまで全取得
-    let mut articles = vec![];
-    let mut next_page_token_for_playlistitems = "".to_string();
-    for playlist in playlists {
+}
+
+impl YoutubeCrawler {
+    pub fn new(api_key: String, channel_id: String) -> Self {
+        let client = reqwest::Client::new();
+        YoutubeCrawler {
+            client,
+            api_key,
+            channel_id,
+            oauth: None,
+        }
+    }
+
+    /// oauthの設定がなければapi keyのみで動かす。
+    pub fn with_oauth_from_env(self, pool: DbPool) -> Result<Self, MyError> {
+        if env_var(env_key::YOUTUBE_OAUTH_CLIENT_ID).is_err() {
+            return Ok(self);
+        }
+        Ok(self.with_oauth(YoutubeOAuth::from_env(pool)?))
+    }
+
+    pub fn with_oauth(self, oauth: YoutubeOAuth) -> Self {
+        YoutubeCrawler {
+            oauth: Some(oauth),
+            ..self
+        }
+    }
+
+    /// 未認可ならapi keyで公開playlistのみ取得する。
+    /// oauthを設定しているのにapi keyになったら、取得できる範囲が狭いのでtrueも返す。
+    async fn credential(&self) -> Result<(Credential, bool), MyError> {
+        if let Some(oauth) = &self.oauth {
+            match oauth.access_token().await? {
+                Some(access_token) => return Ok((Credential::Bearer(access_token), false)),
+                None => {
+                    log::warn!("youtube is not authorized. crawl public playlists only");
+                    return Ok((Credential::ApiKey(self.api_key.clone()), true));
+                }
+            }
+        }
+        Ok((Credential::ApiKey(self.api_key.clone()), false))
+    }
+
+    /// playlist一覧と取得したpage数を返す。
+    async fn fetch_playlists(
+        &self,
+        credential: &Credential,
+    ) -> Result<(Vec<PlayList>, i32), MyError> {
+        let mut playlists = vec![];
+        if let Credential::Bearer(_) = credential {
+            playlists.push(PlayList {
+                id: LIKED_VIDEOS_PLAYLIST_ID.to_string(),
+                snippet: PlayListSnippet {
+                    title: "Liked videos".to_string(),
+                },
+            });
+        }
+        let mut pages = 0;
+        let mut next_page_token_for_playlists = "".to_string();
+        // playlist一覧を取得
+        // nextTokenがなくなるまで全取得
         loop {
-            let playlistitemsres = fetch_youtube_items(
-                &client,
-                &api_key,
-                &playlist.id,
-                &next_page_token_for_playlistitems,
+            let mut playlistres = fetch_youtube_playlists(
+                &self.client,
+                credential,
+                &self.channel_id,
+                &next_page_token_for_playlists,
             )
             .await?;
-            let mut playlistitems = playlistitemsres
-                .items
+            pages += 1;
+            playlists.append(&mut playlistres.items);
+            match playlistres.next_page_token {
+                Some(t) => next_page_token_for_playlists = t,
+                None => break,
+            }
+        }
+        Ok((playlists, pages))
+    }
+
+    /// 各playlist一覧からitemを取得する。stateはplaylist idをfeedとして持つ。
+    /// playlistの並び順は追加日時順とは限らないので全pageを見て、
+    /// 前回見えた最新のitemより後に追加されたitemだけ返す。
+    async fn fetch_items(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let (credential, mut partial) = self.credential().await?;
+        let (playlists, mut pages) = self.fetch_playlists(&credential).await?;
+        let mut articles = vec![];
+        let mut new_states = vec![];
+        for playlist in playlists {
+            let state = CrawlState::find(states, self.media(), &playlist.id);
+            let mut playlist_items = vec![];
+            // nextTokenがなくなるまで全取得
+            let mut next_page_token_for_playlistitems = "".to_string();
+            loop {
+                let mut playlistitemsres = fetch_youtube_items(
+                    &self.client,
+                    &credential,
+                    &playlist.id,
+                    &next_page_token_for_playlistitems,
+                )
+                .await?;
+                pages += 1;
+                playlist_items.append(&mut playlistitemsres.items);
+
+                match playlistitemsres.next_page_token {
+                    Some(t) => next_page_token_for_playlistitems = t,
+                    None => break,
+                }
+            }
+            // 追加日時が読めないitemはlogに残して取り込まない。
+            let playlist_items = playlist_items
+                .into_iter()
+                .filter_map(|item| match item.added_at() {
+                    Ok(added_at) => Some((added_at, item)),
+                    Err(err) => {
+                        log::warn!("skipped invalid item: {:?}", err);
+                        partial = true;
+                        None
+                    }
+                })
+                .collect::<Vec<(DateTime<Utc>, PlayListItem)>>();
+            // 差分はplaylistに追加された日時で判定する。
+            // 前回の最新itemがplaylistから消えていたら全件を対象にする。
+            let watermark = playlist_items
+                .iter()
+                .find(|(_, item)| Some(&item.id) == state.last_item_id.as_ref())
+                .map(|(added_at, _)| *added_at);
+            let mut new_items = vec![];
+            let mut newest: Option<(DateTime<Utc>, String)> = None;
+            for (added_at, item) in playlist_items {
+                if newest.as_ref().is_none_or(|(at, _)| added_at > *at) {
+                    newest = Some((added_at, item.id.clone()));
+                }
+                if watermark.is_none_or(|w| added_at > w) {
+                    new_items.push(item);
+                }
+            }
+            // 新しく追加されたものだけ動画の詳細を取得する。
+            let video_ids = new_items
                 .iter()
-                .map(|playlistitem| {
-                    playlistitem.to_article(
-                        "media".to_string(),
-                        crawled_at.clone(),
-                        playlist.snippet.title.clone(),
+                .map(|item| item.content_details.video_id.clone())
+                .collect::<Vec<String>>();
+            let (videos, video_pages) = self.fetch_videos(&credential, &video_ids).await?;
+            pages += video_pages;
+            let mut new_articles = new_items
+                .iter()
+                .filter_map(|item| {
+                    skip_invalid(
+                        &mut partial,
+                        item.to_article(
+                            self.media(),
+                            crawled_at,
+                            playlist.snippet.title.clone(),
+                            videos.get(&item.content_details.video_id),
+                        ),
                     )
                 })
                 .collect::<Vec<Article>>();
-            articles.append(&mut playlistitems);
+            let page_token = Some(next_page_token_for_playlistitems).filter(|t| !t.is_empty());
+            new_states.push(state.advance(
+                newest.map(|(_, id)| id),
+                page_token,
+                crawled_at,
+                new_articles.len(),
+            ));
+            articles.append(&mut new_articles);
+        }
+        Ok(Crawled {
+            articles,
+            states: new_states,
+            pages,
+            partial,
+        })
+    }
 
-            match playlistitemsres.next_page_token {
-                Some(t) => next_page_token_for_playlistitems = t,
-                None => break,
+    /// videos.listは1回に50件まで。削除、非公開の動画は返らない。
+    async fn fetch_videos(
+        &self,
+        credential: &Credential,
+        video_ids: &[String],
+    ) -> Result<(HashMap<String, Video>, i32), MyError> {
+        let mut videos = HashMap::new();
+        let mut pages = 0;
+        for ids in video_ids.chunks(VIDEOS_PER_REQUEST) {
+            let res = credential
+                .apply(
+                    self.client
+                        .get(YOUTUBE_API_BASE_URL.to_owned() + "v3/videos"),
+                )
+                .query(&[
+                    ("id", ids.join(",").as_str()),
+                    ("part", "snippet"),
+                    ("part", "contentDetails"),
+                    ("part", "statistics"),
+                    ("maxResults", "50"),
+                ])
+                .send()
+                .await?
+                .text()
+                .await?;
+            pages += 1;
+            let videores = serde_json::from_str::<VideoRes>(&res)?;
+            for video in videores.items {
+                videos.insert(video.id.clone(), video);
             }
         }
+        Ok((videos, pages))
+    }
+}
+
+#[async_trait]
+impl Crawl for YoutubeCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let api_key = env_var(env_key::YOUTUBE_API_KEY)?;
+        let channel_id = env_var(env_key::YOUTUBE_CHANNEL_ID)?;
+        Ok(YoutubeCrawler::new(api_key, channel_id))
+    }
+
+    /// accountはchannel id。oauthのtokenはmediaに1つなので環境変数のaccountでのみ使う。
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        let api_key = credential_or_env(source, env_key::YOUTUBE_API_KEY)?;
+        Ok(YoutubeCrawler::new(api_key, source.account.clone()))
+    }
+
+    fn media(&self) -> Media {
+        Media::Youtube
     }
 
-    Ok(articles)
+    /// 認可済みなら高く評価した動画と非公開playlistも対象。
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_items(&[]).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_items(states).await
+    }
 }
+
 /// if items exists then return next_page_token
 async fn fetch_youtube_items(
     client: &Client,
-    api_key: &str,
+    credential: &Credential,
     playlist_id: &str,
     page_token: &str,
 ) -> Result<PlayListItemRes, MyError> {
-    let raw_res = client
-        .get(YOUTUBE_API_BASE_URL.to_owned() + "v3/playlistItems")
+    let raw_res = credential
+        .apply(client.get(YOUTUBE_API_BASE_URL.to_owned() + "v3/playlistItems"))
         .query(&[
-            ("key", api_key),
             ("playlistId", playlist_id),
             ("part", "id"),
             ("part", "snippet"),
@@ -94,17 +306,21 @@ async fn fetch_youtube_items(
     Ok(serde_json::from_str::<PlayListItemRes>(&raw_res)?)
 }
 
+/// 認可済みなら認可したuserのplaylist(非公開を含む)。
 async fn fetch_youtube_playlists(
     client: &Client,
-    api_key: &str,
+    credential: &Credential,
     channel_id: &str,
     page_token: &str,
 ) -> Result<PlayListRes, MyError> {
-    let res = client
-        .get(YOUTUBE_API_BASE_URL.to_owned() + "v3/playlists")
+    let owner = match credential {
+        Credential::ApiKey(_) => ("channelId", channel_id),
+        Credential::Bearer(_) => ("mine", "true"),
+    };
+    let res = credential
+        .apply(client.get(YOUTUBE_API_BASE_URL.to_owned() + "v3/playlists"))
         .query(&[
-            ("key", api_key),
-            ("channelId", channel_id),
+            owner,
             ("part", "id"),
             ("part", "snippet"),
             ("pageToken", page_token),
@@ -150,22 +366,56 @@ struct PlayListItem {
 }
 
 impl PlayListItem {
-    pub fn to_article(&self, media: String, crawled_at: String, playlist_name: String) -> Article {
-        Article {
-            id: self.id.clone(),
-            title: self.snippet.title.clone(),
-            // authorが取れない。
-            author: playlist_name,
-            media,
-            url: format!(
-                "https://www.youtube.com/watch?v={}",
-                self.content_details.video_id
-            ),
-            summary: self.snippet.description.clone(),
-            // publiced_atはリストに入れられた日なので、コンテンツの作成日ではないが、やむをえず
-            created_at: DatetimeFormatter::youtube_to(&self.snippet.published_at),
-            crawled_at,
+    /// playlistに追加された日時
+    fn added_at(&self) -> Result<DateTime<Utc>, MyError> {
+        DatetimeFormatter::youtube_to(&self.snippet.published_at)
+    }
+
+    /// videoがなければ削除か非公開。playlistの情報だけで残して、unavailableにする。
+    fn to_article(
+        &self,
+        media: Media,
+        crawled_at: DateTime<Utc>,
+        playlist_name: String,
+        video: Option<&Video>,
+    ) -> Result<Article, MyError> {
+        let mut article = Article {
+            unavailable: true,
+            ..Article::new(
+                media,
+                self.id.clone(),
+                self.snippet.title.clone(),
+                // 動画が見られなければchannelがわからない。
+                playlist_name,
+                format!(
+                    "https://www.youtube.com/watch?v={}",
+                    self.content_details.video_id
+                ),
+                self.snippet.description.clone(),
+                // 動画が見られなければplaylistに追加された日時。
+                self.added_at()?,
+                crawled_at,
+            )
+        };
+        if let Some(video) = video {
+            article.author = video.snippet.channel_title.clone();
+            article.created_at = DatetimeFormatter::youtube_to(&video.snippet.published_at)?;
+            article.thumbnail_url = video.snippet.thumbnails.best_url();
+            article.duration_seconds = parse_duration(&video.content_details.duration);
+            article.view_count = video
+                .statistics
+                .as_ref()
+                .and_then(|statistics| statistics.view_count.as_ref())
+                .and_then(|view_count| view_count.parse().ok());
+            article.comment_count = video
+                .statistics
+                .as_ref()
+                .and_then(|statistics| statistics.comment_count.as_ref())
+                .and_then(|comment_count| comment_count.parse().ok());
+            article.tags = normalize_tags(video.snippet.tags.clone());
+            article.unavailable = false;
         }
+        Ok(article)
     }
 }
 
@@ -184,28 +434,233 @@ struct ContentDetail {
     video_id: String,
 }
 
-pub async fn youtube_crawl_authorized() -> Result<HttpResponse, MyError> {
-    // korewo jissou
-    // https://developers.google.com/youtube/v3/guides/auth/server-side-web-apps?hl=ja#httprest
-    let oauth_client =
-        env::var("YOUTUBE_OAUTH_CLIENT_ID").expect("youtube oauth client id not set");
-    let client = reqwest::Client::new();
-    let url = "https://accounts.google.com/o/oauth2/v2/auth";
-    let state = "hogehoge".to_string();
-    let params = [
-        ("client_id", oauth_client),
-        ("redirect_url", "http://localhost:8000".to_string()),
-        ("response_type", "code".to_string()),
-        (
-            "scope",
-            "https://www.googleapis.com/auth/youtube.readonly".to_string(),
-        ),
-        ("state", state),
-        ("include_granted_scopes", true.to_string()), // ("access_type",),
-                                                      // ("state",),
-    ];
-    let res = client.get(url).query(&params).send().await?.text().await?;
-    Ok(HttpResponse::Ok()
-        .content_type("text/html; charset=utf-8")
-        .body(res))
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct VideoRes {
+    items: Vec<Video>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Video {
+    id: String,
+    snippet: VideoSnippet,
+    content_details: VideoContentDetail,
+    /// 統計を非公開にしている動画もある。
+    statistics: Option<VideoStatistics>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VideoSnippet {
+    /// 動画の公開日時
+    published_at: String,
+    channel_title: String,
+    thumbnails: Thumbnails,
+    /// 投稿者がつけたtag。ないと省略される。
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Thumbnails {
+    default: Option<Thumbnail>,
+    medium: Option<Thumbnail>,
+    high: Option<Thumbnail>,
+}
+
+impl Thumbnails {
+    fn best_url(&self) -> Option<String> {
+        self.high
+            .as_ref()
+            .or(self.medium.as_ref())
+            .or(self.default.as_ref())
+            .map(|thumbnail| thumbnail.url.clone())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Thumbnail {
+    url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct VideoContentDetail {
+    /// PT1H2M3S
+    duration: String,
+}
+
+/// 数値は文字列で返る。
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct VideoStatistics {
+    view_count: Option<String>,
+    comment_count: Option<String>,
+}
+
+/// ISO 8601のduration(P1DT2H3M4S)を秒にする。配信中の動画はP0Dになる。
+fn parse_duration(duration: &str) -> Option<i32> {
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in duration.strip_prefix('P')?.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {}
+            unit => {
+                let value = number.parse::<i32>().ok()?;
+                number.clear();
+                seconds += value
+                    * match unit {
+                        'D' => 24 * 60 * 60,
+                        'H' => 60 * 60,
+                        'M' => 60,
+                        'S' => 1,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    Some(seconds)
+}
+
+/// authorization code flow。
+/// https://developers.google.com/youtube/v3/guides/auth/server-side-web-apps
+#[derive(Clone)]
+pub struct YoutubeOAuth {
+    client: Client,
+    client_id: String,
+    client_secret: String,
+    /// /api/auth/youtube/callback
+    redirect_uri: String,
+    pool: DbPool,
+}
+
+/// client secretは出さない。
+impl fmt::Debug for YoutubeOAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("YoutubeOAuth")
+            .field("client_id", &self.client_id)
+            .field("redirect_uri", &self.redirect_uri)
+            .finish()
+    }
+}
+
+impl YoutubeOAuth {
+    pub fn from_env(pool: DbPool) -> Result<Self, MyError> {
+        Ok(YoutubeOAuth {
+            client: reqwest::Client::new(),
+            client_id: env_var(env_key::YOUTUBE_OAUTH_CLIENT_ID)?,
+            client_secret: env_var(env_key::YOUTUBE_OAUTH_CLIENT_SECRET)?,
+            redirect_uri: env_var(env_key::YOUTUBE_OAUTH_REDIRECT_URI)?,
+            pool,
+        })
+    }
+
+    /// 認可画面のurl。refresh tokenを受け取れるようにofflineで毎回同意を求める。
+    pub fn authorize_url(&self) -> Result<String, MyError> {
+        let state = oauth::issue_state(Media::Youtube);
+        let url = Url::parse_with_params(
+            GOOGLE_AUTH_URL,
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("response_type", "code"),
+                ("scope", YOUTUBE_READONLY_SCOPE),
+                ("state", state.as_str()),
+                ("access_type", "offline"),
+                ("prompt", "consent"),
+                ("include_granted_scopes", "true"),
+            ],
+        )
+        .map_err(|_| MyError::InternalServerError)?;
+        Ok(url.to_string())
+    }
+
+    /// callbackで受け取ったcodeをtokenに交換して保存する。
+    pub async fn exchange(&self, code: &str, state: &str) -> Result<(), MyError> {
+        oauth::verify_state(Media::Youtube, state)?;
+        let token_res = self
+            .request_token(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", self.redirect_uri.as_str()),
+            ])
+            .await?;
+        let conn = self.pool.get()?;
+        store::oauth_token::store(&conn, &token_res.to_token(None))
+    }
+
+    /// 期限切れならrefresh tokenで更新する。認可されていなければNone。
+    pub async fn access_token(&self) -> Result<Option<String>, MyError> {
+        let token = {
+            let conn = self.pool.get()?;
+            store::oauth_token::find(&conn, &Media::Youtube)?
+        };
+        let token = match token {
+            Some(token) => token,
+            None => return Ok(None),
+        };
+        if !token.is_expired() {
+            return Ok(Some(token.access_token));
+        }
+        let refresh_token = token.refresh_token.ok_or_else(|| {
+            MyError::Unauthorized(
+                json!({"error":"youtube refresh token is not stored. authorize again"}),
+            )
+        })?;
+        let token_res = self
+            .request_token(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.as_str()),
+            ])
+            .await?;
+        let token = token_res.to_token(Some(refresh_token));
+        let conn = self.pool.get()?;
+        store::oauth_token::store(&conn, &token)?;
+        Ok(Some(token.access_token))
+    }
+
+    async fn request_token(&self, params: &[(&str, &str)]) -> Result<TokenRes, MyError> {
+        let res = self
+            .client
+            .post(GOOGLE_TOKEN_URL)
+            .form(
+                &[
+                    ("client_id", self.client_id.as_str()),
+                    ("client_secret", self.client_secret.as_str()),
+                ]
+                .iter()
+                .chain(params.iter())
+                .collect::<Vec<&(&str, &str)>>(),
+            )
+            .send()
+            .await?;
+        if !res.status().is_success() {
+            let body = res.text().await?;
+            return Err(MyError::Unauthorized(
+                json!({ "error": format!("youtube token request failed: {}", body) }),
+            ));
+        }
+        Ok(res.json::<TokenRes>().await?)
+    }
+}
+
+/// google token endpoint response schema.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct TokenRes {
+    access_token: String,
+    /// 秒
+    expires_in: i64,
+    refresh_token: Option<String>,
+}
+
+impl TokenRes {
+    /// 更新時はrefresh tokenが返らないので、前のものを引き継ぐ。
+    fn to_token(&self, refresh_token: Option<String>) -> OAuthToken {
+        OAuthToken {
+            media: Media::Youtube,
+            access_token: self.access_token.clone(),
+            refresh_token: self.refresh_token.clone().or(refresh_token),
+            expires_at: Utc::now() + Duration::seconds(self.expires_in),
+        }
+    }
 }
@@ -0,0 +1,369 @@
+use crate::article::{normalize_tags, Article, DatetimeFormatter};
+use crate::constants::env_key;
+use crate::utils::errors::MyError;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{self, Client};
+use serde::{Deserialize, Serialize};
+
+use super::{env_var, skip_invalid, Crawl, CrawlState, Crawled, Media, Source};
+
+const ZENN_BASE_URL: &str = "https://zenn.dev";
+
+/// feedごとにcrawl stateを持つ。
+const LIKES_FEED: &str = "likes";
+const ARTICLES_FEED: &str = "articles";
+
+#[derive(Debug, Clone)]
+pub struct ZennCrawler {
+    client: Client,
+    user_name: String,
+}
+
+impl ZennCrawler {
+    pub fn new(user_name: String) -> Self {
+        let client = reqwest::Client::new();
+        ZennCrawler { client, user_name }
+    }
+
+    fn feed_url(&self, feed: &str) -> String {
+        match feed {
+            LIKES_FEED => format!(
+                "{}/api/users/{}/liked_articles",
+                ZENN_BASE_URL, self.user_name
+            ),
+            _ => format!(
+                "{}/api/articles?username={}&order=latest",
+                ZENN_BASE_URL, self.user_name
+            ),
+        }
+    }
+
+    /// 新しい順にnext_pageで辿る。
+    /// stateのlast_item_id(前回見えた最新のslug)に到達したらそれ以降は取得しない。
+    async fn fetch_until(
+        &self,
+        state: CrawlState,
+        crawled_at: DateTime<Utc>,
+    ) -> Result<Crawled, MyError> {
+        let mut page_num = Some(1);
+        let mut articles = vec![];
+        let mut partial = false;
+        let mut pages = 0;
+        while let Some(page) = page_num {
+            let res = self
+                .client
+                .get(self.feed_url(&state.feed))
+                .query(&[("page", page)])
+                .send()
+                .await?
+                .json::<ArticlesRes>()
+                .await?;
+            pages += 1;
+            let (new_articles, reached) =
+                take_until_last(&res.articles, state.last_item_id.as_ref());
+            for zenn_article in new_articles.iter() {
+                let topics = self.fetch_topics(&zenn_article.slug).await?;
+                articles.extend(skip_invalid(
+                    &mut partial,
+                    zenn_article.to_article(self.media(), crawled_at, &topics),
+                ));
+            }
+            if reached {
+                break;
+            }
+            page_num = res.next_page;
+        }
+        let state = state.advance(
+            articles.first().map(|article| article.source_id.clone()),
+            None,
+            crawled_at,
+            articles.len(),
+        );
+        Ok(Crawled {
+            articles,
+            states: vec![state],
+            pages,
+            partial,
+        })
+    }
+
+    /// 一覧にはtopicが含まれないので記事ごとに取得する。
+    async fn fetch_topics(&self, slug: &str) -> Result<Vec<ZennTopic>, MyError> {
+        let res = self
+            .client
+            .get(format!("{}/api/articles/{}", ZENN_BASE_URL, slug))
+            .send()
+            .await?
+            .json::<ArticleRes>()
+            .await?;
+        Ok(res.article.topics)
+    }
+
+    async fn fetch_feeds(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        let crawled_at = Utc::now();
+        let mut crawled = Crawled::default();
+        for feed in [LIKES_FEED, ARTICLES_FEED] {
+            let state = CrawlState::find(states, self.media(), feed);
+            crawled.append(self.fetch_until(state, crawled_at).await?);
+        }
+        Ok(crawled)
+    }
+}
+
+#[async_trait]
+impl Crawl for ZennCrawler {
+    fn from_env() -> Result<Self, MyError> {
+        let user_name = env_var(env_key::ZENN_USER_NAME)?;
+        Ok(ZennCrawler::new(user_name))
+    }
+
+    fn from_source(source: &Source) -> Result<Self, MyError> {
+        Ok(ZennCrawler::new(source.account.clone()))
+    }
+
+    fn media(&self) -> Media {
+        Media::Zenn
+    }
+
+    async fn fetch(&self) -> Result<Crawled, MyError> {
+        self.fetch_feeds(&[]).await
+    }
+
+    async fn fetch_to_update(&self, states: &[CrawlState]) -> Result<Crawled, MyError> {
+        self.fetch_feeds(states).await
+    }
+}
+
+/// 新しい順のarticlesのうち、last_item_idより新しいもの。last_item_idに到達したかも返す。
+fn take_until_last<'a>(
+    articles: &'a [ZennArticle],
+    last_item_id: Option<&String>,
+) -> (&'a [ZennArticle], bool) {
+    match articles
+        .iter()
+        .position(|article| Some(&article.slug) == last_item_id)
+    {
+        Some(i) => (&articles[..i], true),
+        None => (articles, false),
+    }
+}
+
+/// zenn api response schema.
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ArticlesRes {
+    articles: Vec<ZennArticle>,
+    next_page: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ArticleRes {
+    article: ZennArticleDetail,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ZennArticle {
+    slug: String,
+    title: String,
+    emoji: String,
+    path: String,
+    published_at: String,
+    user: ZennUser,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ZennArticleDetail {
+    #[serde(default)]
+    topics: Vec<ZennTopic>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ZennTopic {
+    name: String,
+    display_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct ZennUser {
+    username: String,
+    name: String,
+}
+
+impl ZennArticle {
+    fn to_article(
+        &self,
+        media: Media,
+        crawled_at: DateTime<Utc>,
+        topics: &[ZennTopic],
+    ) -> Result<Article, MyError> {
+        let topics = topics
+            .iter()
+            .map(|topic| topic.display_name.clone())
+            .collect::<Vec<String>>();
+        let tags = normalize_tags(topics.clone());
+        Ok(Article {
+            tags,
+            ..Article::new(
+                media,
+                self.slug.clone(),
+                self.title.clone(),
+                self.user.name.clone(),
+                format!("{}{}", ZENN_BASE_URL, self.path),
+                // summaryはないので、emojiとtopic.
+                format!("{} {}", self.emoji, topics.join(", "))
+                    .trim_end()
+                    .to_string(),
+                DatetimeFormatter::zenn_to(&self.published_at)?,
+                crawled_at,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const LIKED_ARTICLES: &str = include_str!("../../tests/fixtures/zenn/liked_articles.json");
+    const ARTICLES: &str = include_str!("../../tests/fixtures/zenn/articles.json");
+    const ARTICLE: &str = include_str!("../../tests/fixtures/zenn/article.json");
+
+    fn articles_res(json: &str) -> ArticlesRes {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn topics() -> Vec<ZennTopic> {
+        serde_json::from_str::<ArticleRes>(ARTICLE)
+            .unwrap()
+            .article
+            .topics
+    }
+
+    fn slugs(articles: &[ZennArticle]) -> Vec<&str> {
+        articles
+            .iter()
+            .map(|article| article.slug.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn to_article_uses_emoji_and_topics_as_summary() {
+        let res = articles_res(LIKED_ARTICLES);
+        let crawled_at = Utc::now();
+        let article = res.articles[0]
+            .to_article(Media::Zenn, crawled_at, &topics())
+            .unwrap();
+        assert_eq!(article.id, "zenn:a1b2c3d4e5f6a7");
+        assert_eq!(article.source_id, "a1b2c3d4e5f6a7");
+        assert_eq!(article.title, "Rustで非同期crawlerを書く");
+        assert_eq!(article.author, "Alice");
+        assert_eq!(
+            article.url,
+            "https://zenn.dev/alice/articles/a1b2c3d4e5f6a7"
+        );
+        assert_eq!(article.summary, "🦀 Rust, tokio, rust");
+        assert_eq!(article.crawled_at, crawled_at);
+    }
+
+    #[test]
+    fn to_article_without_topics_has_only_emoji() {
+        let res = articles_res(LIKED_ARTICLES);
+        let article = res.articles[1]
+            .to_article(Media::Zenn, Utc::now(), &[])
+            .unwrap();
+        assert_eq!(article.summary, "📄");
+        assert!(article.tags.is_empty());
+    }
+
+    #[test]
+    fn to_article_normalizes_topic_tags() {
+        let res = articles_res(LIKED_ARTICLES);
+        let article = res.articles[0]
+            .to_article(Media::Zenn, Utc::now(), &topics())
+            .unwrap();
+        assert_eq!(article.tags, vec!["Rust", "tokio"]);
+    }
+
+    #[test]
+    fn to_article_converts_published_at_to_utc() {
+        let res = articles_res(LIKED_ARTICLES);
+        let article = res.articles[0]
+            .to_article(Media::Zenn, Utc::now(), &[])
+            .unwrap();
+        assert_eq!(
+            article.created_at,
+            Utc.with_ymd_and_hms(2022, 12, 1, 12, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn to_article_rejects_invalid_published_at() {
+        let mut zenn_article = articles_res(LIKED_ARTICLES).articles[0].clone();
+        zenn_article.published_at = "2022/12/01 21:00".to_string();
+        assert!(zenn_article
+            .to_article(Media::Zenn, Utc::now(), &[])
+            .is_err());
+    }
+
+    #[test]
+    fn take_until_last_takes_all_on_first_crawl() {
+        let res = articles_res(LIKED_ARTICLES);
+        let (new_articles, reached) = take_until_last(&res.articles, None);
+        assert_eq!(
+            slugs(new_articles),
+            vec!["a1b2c3d4e5f6a7", "b2c3d4e5f6a7b8", "c3d4e5f6a7b8c9"]
+        );
+        assert!(!reached);
+    }
+
+    #[test]
+    fn take_until_last_stops_before_last_item() {
+        let res = articles_res(LIKED_ARTICLES);
+        let last_item_id = "c3d4e5f6a7b8c9".to_string();
+        let (new_articles, reached) = take_until_last(&res.articles, Some(&last_item_id));
+        assert_eq!(
+            slugs(new_articles),
+            vec!["a1b2c3d4e5f6a7", "b2c3d4e5f6a7b8"]
+        );
+        assert!(reached);
+    }
+
+    #[test]
+    fn take_until_last_takes_page_without_last_item() {
+        let res = articles_res(LIKED_ARTICLES);
+        let last_item_id = "z9y8x7w6v5u4t3".to_string();
+        let (new_articles, reached) = take_until_last(&res.articles, Some(&last_item_id));
+        assert_eq!(new_articles.len(), 3);
+        assert!(!reached);
+    }
+
+    /// likesの最新が自分の記事でも、articlesのfeedは自身のstateで辿る。
+    #[test]
+    fn take_until_last_keeps_feeds_independent_for_same_slug() {
+        let likes = articles_res(LIKED_ARTICLES);
+        let articles = articles_res(ARTICLES);
+        let likes_last = "a1b2c3d4e5f6a7".to_string();
+        let (new_likes, likes_reached) = take_until_last(&likes.articles, Some(&likes_last));
+        assert!(new_likes.is_empty());
+        assert!(likes_reached);
+
+        let (new_articles, articles_reached) = take_until_last(&articles.articles, None);
+        assert_eq!(
+            slugs(new_articles),
+            vec!["d4e5f6a7b8c9d0", "a1b2c3d4e5f6a7"]
+        );
+        assert!(!articles_reached);
+
+        // どちらのfeedから取得しても同じarticleとして保存される。
+        let crawled_at = Utc::now();
+        let liked = likes.articles[0]
+            .to_article(Media::Zenn, crawled_at, &[])
+            .unwrap();
+        let written = new_articles[1]
+            .to_article(Media::Zenn, crawled_at, &[])
+            .unwrap();
+        assert_eq!(liked, written);
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::OnceLock;
+
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+
+use crate::article::Article;
+use crate::crawl::run::CrawlRun;
+
+/// subscriptionに流す。保存してからpublishする。
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// CrawlRunより大きいのでboxにする。
+    ArticleAdded(Box<Article>),
+    CrawlProgress(CrawlRun),
+}
+
+/// 受信が追いつかなければ古いeventから捨てられる。
+const CAPACITY: usize = 1024;
+
+fn sender() -> &'static Sender<Event> {
+    static SENDER: OnceLock<Sender<Event>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// subscriberがいなければ捨てる。
+pub fn publish(event: Event) {
+    let _ = sender().send(event);
+}
+
+pub fn subscribe() -> Receiver<Event> {
+    sender().subscribe()
+}
+
+/// 取りこぼしたeventは飛ばして、次のeventを待つ。
+pub async fn recv(receiver: &mut Receiver<Event>) -> Option<Event> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(skipped)) => log::warn!("{} events are skipped", skipped),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
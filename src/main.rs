@@ -1,105 +1,305 @@
 use actix_cors::Cors;
 use actix_web::web::{get, post, Data};
-use actix_web::{guard, http, middleware, web, App, HttpResponse, HttpServer, Result};
+use actix_web::{guard, http, middleware, web, App, HttpRequest, HttpResponse, HttpServer, Result};
+use async_graphql::futures_util::{stream, Stream, StreamExt};
 use async_graphql::EmptyMutation;
 use async_graphql::{
+    connection::{self, Connection, Edge, OpaqueCursor},
     http::{playground_source, GraphQLPlaygroundConfig},
-    EmptySubscription, Object, Schema,
+    Context, Object, Schema, SimpleObject, Subscription,
 };
-use diesel::r2d2::ConnectionManager;
-use diesel::MysqlConnection;
 use dotenv::dotenv;
 
 #[macro_use]
 extern crate diesel;
 mod article;
+mod canonical;
 mod constants;
 mod crawl;
+mod event;
 mod output;
 mod schema;
+mod search;
 mod store;
 mod utils;
-use article::Article;
-use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
-use r2d2::PooledConnection;
+use article::{Article, ArticleFilter, ArticleOrder};
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
+use crawl::run::{CrawlMode, CrawlRun};
+use crawl::youtube::YoutubeOAuth;
+use crawl::{Media, Registry, Source};
+use event::Event;
+use search::SearchHit;
+use serde::Deserialize;
+use serde_json::json;
+use store::model::ArticleCursor;
+use utils::db::DbPool;
 use utils::errors::MyError;
+use utils::state::AppState;
 
 struct QueryRoot;
 
-fn get_conn() -> Result<PooledConnection<ConnectionManager<MysqlConnection>>, MyError> {
-    let pool = utils::db::establish_connection();
-    let conn = pool.get()?;
-    Ok(conn)
-}
-
 #[Object]
 impl QueryRoot {
-    async fn scan(&self) -> Result<Vec<Article>, MyError> {
-        let conn = get_conn()?;
+    #[graphql(deprecation = "use articles")]
+    async fn scan(&self, ctx: &Context<'_>) -> Result<Vec<Article>, MyError> {
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
         let res = store::model::scan(&conn)?;
         Ok(res)
     }
-    async fn is_latest(&self, media: String) -> Result<bool, MyError> {
-        let conn = get_conn()?;
-        let stored_one = store::model::latest_one(&conn, &media)?;
-        let crawled_one = crawl::qiita::latest_one().await?;
-        Ok(stored_one == crawled_one)
+    /// afterのcursorから、first件ずつ取得する。filterを省略すると全件。
+    async fn articles(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        #[graphql(default)] filter: ArticleFilter,
+        #[graphql(default)] order_by: ArticleOrder,
+    ) -> async_graphql::Result<Connection<OpaqueCursor<ArticleCursor>, Article, ArticlesFields>>
+    {
+        let pool = ctx.data_unchecked::<DbPool>().clone();
+        connection::query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<OpaqueCursor<ArticleCursor>>, _, first, _| async move {
+                let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+                let conn = pool.get()?;
+                let total_count = store::model::count(&conn, &filter)?;
+                // 1件多く取得して次のpageがあるか判定する。
+                let mut page = store::model::page(
+                    &conn,
+                    &filter,
+                    order_by,
+                    after.as_deref(),
+                    limit as i64 + 1,
+                )?;
+                let has_next_page = page.len() > limit;
+                page.truncate(limit);
+                let mut connection = Connection::with_additional_fields(
+                    after.is_some(),
+                    has_next_page,
+                    ArticlesFields { total_count },
+                );
+                connection.edges.extend(
+                    page.into_iter()
+                        .map(|(cursor, article)| Edge::new(OpaqueCursor(cursor), article)),
+                );
+                Ok::<_, MyError>(connection)
+            },
+        )
+        .await
+    }
+    /// title、summary、authorの全文検索。関連度の高い順。
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        media: Option<Media>,
+        first: Option<i32>,
+        after: Option<String>,
+        #[graphql(default)] include_removed: bool,
+    ) -> async_graphql::Result<Connection<usize, SearchHit, ArticlesFields>> {
+        let pool = ctx.data_unchecked::<DbPool>().clone();
+        connection::query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<usize>, _, first, _| async move {
+                let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+                let offset = after.map(|after| after + 1).unwrap_or(0);
+                let conn = pool.get()?;
+                let total_count =
+                    store::model::search_count(&conn, &query, media, include_removed)?;
+                let hits = store::model::search(
+                    &conn,
+                    &query,
+                    media,
+                    include_removed,
+                    offset as i64,
+                    limit as i64,
+                )?;
+                let mut connection = Connection::with_additional_fields(
+                    offset > 0,
+                    ((offset + hits.len()) as i64) < total_count,
+                    ArticlesFields { total_count },
+                );
+                connection.edges.extend(hits.into_iter().enumerate().map(
+                    |(i, (score, article))| {
+                        Edge::new(offset + i, SearchHit::new(article, score, &query))
+                    },
+                ));
+                Ok::<_, MyError>(connection)
+            },
+        )
+        .await
+    }
+    /// 前回のcrawl以降にsourceへ追加されたものがないか
+    async fn is_latest(&self, ctx: &Context<'_>, media: Media) -> Result<bool, MyError> {
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
+        let states = store::crawl_state::load(&conn, &media)?;
+        let accounts = ctx.data_unchecked::<Registry>().accounts(&media)?;
+        let crawled = crawl::fetch_to_update(&accounts, &states).await?;
+        Ok(crawled.articles.is_empty())
+    }
+    /// 登録したaccount。環境変数で設定したaccountは含まない。
+    async fn sources(
+        &self,
+        ctx: &Context<'_>,
+        media: Option<Media>,
+    ) -> Result<Vec<Source>, MyError> {
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
+        let res = store::source::all(&conn, media.as_ref())?;
+        Ok(res)
+    }
+    async fn crawl_run(&self, ctx: &Context<'_>, id: String) -> Result<CrawlRun, MyError> {
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
+        let res = store::crawl_run::find(&conn, &id)?;
+        Ok(res)
+    }
+    /// 新しい順
+    async fn crawl_runs(
+        &self,
+        ctx: &Context<'_>,
+        media: Option<Media>,
+        #[graphql(default = 20)] limit: i64,
+    ) -> Result<Vec<CrawlRun>, MyError> {
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
+        let res = store::crawl_run::recent(&conn, media.as_ref(), limit)?;
+        Ok(res)
     }
 }
 
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+#[derive(SimpleObject)]
+struct ArticlesFields {
+    /// pageに関係なく、条件に一致する件数
+    total_count: i64,
+}
+
 struct MutationRoot;
 
 #[Object]
 impl MutationRoot {
-    async fn qiita_crawl(&self) -> Result<Vec<Article>, MyError> {
-        let res = crawl::qiita::qiita_crawl().await?;
-        let conn = get_conn()?;
-        store::model::store_rdb(&conn, &res);
-        Ok(res)
+    /// 全件取得
+    async fn crawl(&self, ctx: &Context<'_>, media: Media) -> Result<Vec<Article>, MyError> {
+        let registry = ctx.data_unchecked::<Registry>();
+        let pool = ctx.data_unchecked::<DbPool>();
+        crawl::run::run_collecting(registry, pool, media, CrawlMode::Full).await
     }
     /// 差分アップデート
-    /// 追加のみ対応
-    async fn crawl_and_store(&self, media: String) -> Result<Vec<Article>, MyError> {
-        let conn = get_conn()?;
-        let latest_one = store::model::latest_one(&conn, &media)?;
-        let res = crawl::qiita::crawl_to_update(latest_one).await?;
-        store::model::store_rdb(&conn, &res);
-        Ok(res)
+    /// 追加のみ対応。外されたものはstartCrawlのRECONCILEで記録する。
+    async fn crawl_and_store(
+        &self,
+        ctx: &Context<'_>,
+        media: Media,
+    ) -> Result<Vec<Article>, MyError> {
+        let registry = ctx.data_unchecked::<Registry>();
+        let pool = ctx.data_unchecked::<DbPool>();
+        crawl::run::run_collecting(registry, pool, media, CrawlMode::Update).await
     }
 
-    async fn youtube_crawl(&self) -> Result<Vec<Article>, MyError> {
-        let res = crawl::youtube::youtube_crawl_unauthorized().await?;
-        let conn = get_conn()?;
-        store::model::store_rdb(&conn, &res);
-        Ok(res)
+    /// crawlをbackgroundで開始する。既定は差分アップデート。
+    /// 進み具合はcrawlRunで確認する。
+    async fn start_crawl(
+        &self,
+        ctx: &Context<'_>,
+        media: Media,
+        #[graphql(default)] mode: CrawlMode,
+    ) -> Result<CrawlRun, MyError> {
+        let registry = ctx.data_unchecked::<Registry>();
+        let pool = ctx.data_unchecked::<DbPool>();
+        crawl::run::start(registry, pool, media, mode)
     }
 
-    // async fn youtube_crawl_with_auth(&self) -> Result<HttpResponse, MyError> {
-    //     let res = crawl::youtube_crawl_authorized().await?;
-    //     Ok(res)
-    // }
-
-    async fn twitter_crawl(&self) -> Result<Vec<Article>, MyError> {
-        let res = crawl::twitter::twitter_crawl().await?;
-        let conn = get_conn()?;
-        store::model::store_rdb(&conn, &res);
-        Ok(res)
+    /// accountを追加する。次のcrawlから対象になる。
+    /// credentialを省略すると環境変数のtokenなどを使う。
+    async fn add_source(
+        &self,
+        ctx: &Context<'_>,
+        media: Media,
+        label: String,
+        account: String,
+        credential: Option<String>,
+    ) -> Result<Source, MyError> {
+        let source = Source::new(media, label, account, credential);
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
+        store::source::insert(&conn, &source)?;
+        // 設定が足りず登録できなければ、追加しなかったことにする。
+        if let Err(err) = ctx.data_unchecked::<Registry>().register_source(&source) {
+            store::source::delete(&conn, &source.id)?;
+            return Err(err);
+        }
+        Ok(source)
+    }
+    /// 保存済みのarticleは残す。
+    async fn remove_source(&self, ctx: &Context<'_>, id: String) -> Result<bool, MyError> {
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
+        let removed = store::source::delete(&conn, &id)?;
+        ctx.data_unchecked::<Registry>().unregister_source(&id);
+        Ok(removed)
     }
 
-    async fn gen_json_from_store(&self) -> Result<Vec<Article>, MyError> {
-        let conn = get_conn()?;
+    async fn gen_json_from_store(&self, ctx: &Context<'_>) -> Result<Vec<Article>, MyError> {
+        let conn = ctx.data_unchecked::<DbPool>().get()?;
         let res = store::model::scan(&conn)?;
         output::write_json(&res);
         Ok(res)
     }
 }
 
-type OiSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// 新しく保存されたarticle。mediaを省略すると全media。
+    async fn article_added(&self, media: Option<Vec<Media>>) -> impl Stream<Item = Article> {
+        event_stream().filter_map(move |event| {
+            let article = match event {
+                Event::ArticleAdded(article) => Some(*article),
+                _ => None,
+            }
+            .filter(|article| media.as_ref().is_none_or(|m| m.contains(&article.media)));
+            async move { article }
+        })
+    }
+    /// startCrawlで返したrunの開始、取得、終了ごとに流れる。
+    async fn crawl_progress(&self, run_id: String) -> impl Stream<Item = CrawlRun> {
+        event_stream().filter_map(move |event| {
+            let run = match event {
+                Event::CrawlProgress(run) if run.id == run_id => Some(run),
+                _ => None,
+            };
+            async move { run }
+        })
+    }
+}
+
+fn event_stream() -> impl Stream<Item = Event> {
+    stream::unfold(event::subscribe(), |mut receiver| async move {
+        event::recv(&mut receiver)
+            .await
+            .map(|event| (event, receiver))
+    })
+}
+
+type OiSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
 async fn index(schema: Data<OiSchema>, req: GraphQLRequest) -> GraphQLResponse {
     schema.execute(req.into_inner()).await.into()
 }
 
+async fn index_ws(
+    schema: Data<OiSchema>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> Result<HttpResponse> {
+    GraphQLSubscription::new(Schema::clone(&*schema)).start(&req, payload)
+}
+
 async fn index_playground() -> Result<HttpResponse> {
     let source = playground_source(GraphQLPlaygroundConfig::new("/").subscription_endpoint("/"));
     Ok(HttpResponse::Ok()
@@ -107,19 +307,71 @@ async fn index_playground() -> Result<HttpResponse> {
         .body(source))
 }
 
-async fn authorize() -> Result<HttpResponse> {
-    let res = crawl::youtube::youtube_crawl_authorized().await?;
-    Ok(res)
+/// googleの認可画面へredirectする。
+async fn youtube_authorize(app: Data<AppState>) -> Result<HttpResponse, MyError> {
+    let oauth = YoutubeOAuth::from_env(app.pool.clone())?;
+    Ok(HttpResponse::Found()
+        .append_header((http::header::LOCATION, oauth.authorize_url()?))
+        .finish())
+}
+
+#[derive(Deserialize)]
+struct OAuthCallback {
+    code: Option<String>,
+    state: String,
+    error: Option<String>,
+}
+
+/// 認可されたらtokenを保存する。以降のcrawlは非公開playlistも対象になる。
+async fn youtube_oauth_callback(
+    app: Data<AppState>,
+    query: web::Query<OAuthCallback>,
+) -> Result<HttpResponse, MyError> {
+    let code = match (&query.code, &query.error) {
+        (Some(code), None) => code,
+        (_, error) => {
+            return Err(MyError::Unauthorized(
+                json!({ "error": format!("youtube authorization denied: {:?}", error) }),
+            ))
+        }
+    };
+    let oauth = YoutubeOAuth::from_env(app.pool.clone())?;
+    oauth.exchange(code, &query.state).await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body("youtube is authorized. you can close this page."))
 }
 
 #[actix_web::main] // or #[tokio::main]
 async fn main() -> std::io::Result<()> {
-    let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription).finish();
-
+    dotenv().ok();
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("debug"));
 
     let pool = utils::db::establish_connection();
-    dotenv().ok();
+    let registry = Registry::from_env(&pool);
+    match pool
+        .get()
+        .map_err(MyError::from)
+        .and_then(|conn| store::source::all(&conn, None))
+    {
+        Ok(sources) => registry.register_sources(&sources),
+        Err(err) => log::warn!("failed to load sources: {:?}", err),
+    }
+    match pool
+        .get()
+        .map_err(MyError::from)
+        .and_then(|conn| store::model::fill_canonical_urls(&conn))
+    {
+        Ok(0) => {}
+        Ok(count) => log::info!("filled canonical urls of {} articles", count),
+        Err(err) => log::warn!("failed to fill canonical urls: {:?}", err),
+    }
+    crawl::scheduler::spawn(registry.clone(), pool.clone());
+
+    let schema = Schema::build(QueryRoot, MutationRoot, SubscriptionRoot)
+        .data(registry)
+        .data(pool.clone())
+        .finish();
 
     HttpServer::new(move || {
         let cors = Cors::default()
@@ -136,6 +388,13 @@ async fn main() -> std::io::Result<()> {
             .wrap(middleware::Logger::default())
             .configure(api)
             .app_data(Data::new(schema.clone()))
+            .app_data(Data::new(AppState { pool: pool.clone() }))
+            .service(
+                web::resource("/")
+                    .guard(guard::Get())
+                    .guard(guard::Header("upgrade", "websocket"))
+                    .to(index_ws),
+            )
             .service(web::resource("/").guard(guard::Get()).to(index_playground))
             .service(web::resource("/").guard(guard::Post()).to(index))
     })
@@ -149,6 +408,16 @@ pub fn api(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
             .service(web::resource("/").guard(guard::Get()).to(index_playground))
-            .service(web::resource("/").guard(guard::Post()).to(index)), // .service(web::resource("/auth").guard(guard::Post()).to(authorize)),
+            .service(web::resource("/").guard(guard::Post()).to(index))
+            .service(
+                web::resource("/auth/youtube")
+                    .guard(guard::Get())
+                    .to(youtube_authorize),
+            )
+            .service(
+                web::resource("/auth/youtube/callback")
+                    .guard(guard::Get())
+                    .to(youtube_oauth_callback),
+            ),
     );
 }
@@ -1,5 +1,20 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    article_accounts (article_id, account_id) {
+        article_id -> Varchar,
+        account_id -> Varchar,
+        removed_at -> Nullable<Datetime>,
+    }
+}
+
+diesel::table! {
+    article_tags (article_id, tag_id) {
+        article_id -> Varchar,
+        tag_id -> Bigint,
+    }
+}
+
 diesel::table! {
     articles (id) {
         id -> Varchar,
@@ -7,8 +22,85 @@ diesel::table! {
         author -> Varchar,
         media -> Varchar,
         url -> Varchar,
-        summary -> Varchar,
+        summary -> Text,
         created_at -> Datetime,
         crawled_at -> Datetime,
+        discussion_url -> Nullable<Varchar>,
+        score -> Nullable<Integer>,
+        comment_count -> Nullable<Integer>,
+        thumbnail_url -> Nullable<Varchar>,
+        duration_seconds -> Nullable<Integer>,
+        view_count -> Nullable<Bigint>,
+        unavailable -> Bool,
+        metadata -> Nullable<Text>,
+        canonical_url -> Varchar,
+        source_id -> Varchar,
+        removed_at -> Nullable<Datetime>,
+    }
+}
+
+diesel::table! {
+    crawl_runs (id) {
+        id -> Varchar,
+        media -> Varchar,
+        started_at -> Datetime,
+        finished_at -> Nullable<Datetime>,
+        error -> Nullable<Text>,
+        pages_fetched -> Integer,
+        articles_inserted -> Integer,
+        articles_updated -> Integer,
+        articles_unchanged -> Integer,
+        articles_removed -> Integer,
+    }
+}
+
+diesel::table! {
+    crawl_state (media, account_id, feed) {
+        media -> Varchar,
+        account_id -> Varchar,
+        feed -> Varchar,
+        last_item_id -> Nullable<Varchar>,
+        next_page_token -> Nullable<Varchar>,
+        last_crawled_at -> Nullable<Datetime>,
+        item_count -> Integer,
+        etag -> Nullable<Varchar>,
+        last_modified -> Nullable<Varchar>,
     }
 }
+
+diesel::table! {
+    oauth_tokens (media) {
+        media -> Varchar,
+        access_token -> Text,
+        refresh_token -> Nullable<Text>,
+        expires_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    sources (id) {
+        id -> Varchar,
+        media -> Varchar,
+        label -> Varchar,
+        account -> Varchar,
+        credential -> Nullable<Text>,
+        created_at -> Datetime,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Bigint,
+        name -> Varchar,
+    }
+}
+
+diesel::joinable!(article_tags -> tags (tag_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    article_accounts,
+    article_tags,
+    articles,
+    crawl_state,
+    tags,
+);
@@ -0,0 +1,105 @@
+use async_graphql::SimpleObject;
+
+use crate::article::Article;
+
+/// 前後に残す文字数
+const SNIPPET_MARGIN: usize = 40;
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct SearchHit {
+    pub article: Article,
+    /// MySQLのfulltext検索の関連度
+    pub score: f64,
+    /// 一致した語を<em>で囲んだtitle
+    pub title_highlight: String,
+    /// summaryの一致した語の周辺。一致がなければsummaryの先頭。
+    pub snippet: String,
+}
+
+impl SearchHit {
+    pub fn new(article: Article, score: f64, query: &str) -> Self {
+        let terms = terms(query);
+        SearchHit {
+            title_highlight: highlight(&article.title, &terms),
+            snippet: highlight(&snippet(&article.summary, &terms), &terms),
+            article,
+            score,
+        }
+    }
+}
+
+fn terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+/// 大文字小文字は区別しない。
+/// 小文字にするとbyte位置がずれる文字を含むtextは区別する。
+fn find(text: &str, term: &str, from: usize) -> Option<usize> {
+    let lower = text.to_lowercase();
+    let same_layout = lower.chars().count() == text.chars().count()
+        && lower
+            .chars()
+            .zip(text.chars())
+            .all(|(l, t)| l.len_utf8() == t.len_utf8());
+    let haystack = if same_layout { &lower } else { text };
+    haystack[from..].find(term).map(|i| from + i)
+}
+
+fn snippet(text: &str, terms: &[String]) -> String {
+    let chars = text.chars().collect::<Vec<char>>();
+    let first_match = terms
+        .iter()
+        .filter_map(|term| find(text, term, 0))
+        .min()
+        .map(|byte_index| text[..byte_index].chars().count())
+        .unwrap_or(0);
+    let start = first_match.saturating_sub(SNIPPET_MARGIN);
+    let end = (first_match + SNIPPET_MARGIN * 2).min(chars.len());
+    let mut snippet = chars[start..end].iter().collect::<String>();
+    if start > 0 {
+        snippet = format!("…{}", snippet);
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// textはhtml escapeしてから一致箇所を<em>で囲む。
+fn highlight(text: &str, terms: &[String]) -> String {
+    // 一致箇所をbyte単位で集めて、重なりはまとめる。
+    let mut ranges = vec![];
+    for term in terms.iter().filter(|term| !term.is_empty()) {
+        let mut from = 0;
+        while let Some(start) = find(text, term, from) {
+            ranges.push((start, start + term.len()));
+            from = start + term.len();
+        }
+    }
+    ranges.sort();
+    let mut merged: Vec<(usize, usize)> = vec![];
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    let mut highlighted = String::new();
+    let mut cursor = 0;
+    for (start, end) in merged {
+        highlighted.push_str(&escape(&text[cursor..start]));
+        highlighted.push_str(&format!("<em>{}</em>", escape(&text[start..end])));
+        cursor = end;
+    }
+    highlighted.push_str(&escape(&text[cursor..]));
+    highlighted
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::article::Article;
+use crate::schema::article_accounts;
+use crate::utils::errors::MyError;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+
+/// article idごとの保存したaccount。環境変数で設定したaccountと、外したaccountは含まない。
+pub fn load(
+    conn: &MysqlConnection,
+    article_ids: &[String],
+) -> Result<HashMap<String, Vec<String>>, MyError> {
+    if article_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = article_accounts::table
+        .filter(article_accounts::article_id.eq_any(article_ids))
+        .filter(article_accounts::account_id.ne(""))
+        .filter(article_accounts::removed_at.is_null())
+        .order_by((article_accounts::article_id, article_accounts::account_id))
+        .select((article_accounts::article_id, article_accounts::account_id))
+        .load::<(String, String)>(conn)?;
+    let mut accounts = HashMap::<String, Vec<String>>::new();
+    for (article_id, account_id) in rows {
+        accounts.entry(article_id).or_default().push(account_id);
+    }
+    Ok(accounts)
+}
+
+/// articleを取得したaccountを記録する。外したと記録していたら戻す。
+pub fn store_batch(conn: &MysqlConnection, articles: &[Article]) -> Result<(), MyError> {
+    if articles.is_empty() {
+        return Ok(());
+    }
+    let records = articles
+        .iter()
+        .map(ArticleAccountRDB::from_domain)
+        .collect::<Vec<ArticleAccountRDB>>();
+    diesel::insert_or_ignore_into(article_accounts::table)
+        .values(&records)
+        .execute(conn)?;
+    let mut article_ids = HashMap::<String, Vec<String>>::new();
+    for record in records {
+        article_ids
+            .entry(record.account_id)
+            .or_default()
+            .push(record.article_id);
+    }
+    for (account_id, article_ids) in article_ids {
+        diesel::update(article_accounts::table)
+            .filter(article_accounts::account_id.eq(account_id))
+            .filter(article_accounts::article_id.eq_any(article_ids))
+            .filter(article_accounts::removed_at.is_not_null())
+            .set(article_accounts::removed_at.eq(None::<NaiveDateTime>))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// accountが保存しているarticleのうち、article_idsにあるものを外したと記録する。
+pub fn mark_removed(
+    conn: &MysqlConnection,
+    account_id: Option<&str>,
+    article_ids: &[String],
+    removed_at: DateTime<Utc>,
+) -> Result<usize, MyError> {
+    if article_ids.is_empty() {
+        return Ok(0);
+    }
+    let count = diesel::update(article_accounts::table)
+        .filter(article_accounts::account_id.eq(account_id.unwrap_or_default().to_string()))
+        .filter(article_accounts::article_id.eq_any(article_ids))
+        .filter(article_accounts::removed_at.is_null())
+        .set(article_accounts::removed_at.eq(Some(removed_at.naive_utc())))
+        .execute(conn)?;
+    Ok(count)
+}
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "article_accounts"]
+pub struct ArticleAccountRDB {
+    pub article_id: String,
+    /// 環境変数で設定したaccountは空文字。
+    pub account_id: String,
+    /// このaccountのsourceで外された日時。
+    pub removed_at: Option<NaiveDateTime>,
+}
+
+impl ArticleAccountRDB {
+    fn from_domain(article: &Article) -> Self {
+        ArticleAccountRDB {
+            article_id: article.id.clone(),
+            account_id: article.account_id.clone().unwrap_or_default(),
+            removed_at: None,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::crawl::run::CrawlRun;
+use crate::crawl::Media;
+use crate::event::{self, Event};
+use crate::schema::crawl_runs;
+use crate::utils::errors::MyError;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+
+pub fn insert(conn: &MysqlConnection, run: &CrawlRun) -> Result<(), MyError> {
+    CrawlRunRDB::from_domain(run).store(conn)?;
+    event::publish(Event::CrawlProgress(run.clone()));
+    Ok(())
+}
+
+/// 終了時刻、件数とerrorを更新する。
+pub fn update(conn: &MysqlConnection, run: &CrawlRun) -> Result<(), MyError> {
+    CrawlRunRDB::from_domain(run).save(conn)?;
+    event::publish(Event::CrawlProgress(run.clone()));
+    Ok(())
+}
+
+pub fn find(conn: &MysqlConnection, id: &str) -> Result<CrawlRun, MyError> {
+    CrawlRunRDB::find(conn, id)
+}
+
+/// 新しい順。mediaがNoneなら全media。
+pub fn recent(
+    conn: &MysqlConnection,
+    media: Option<&Media>,
+    limit: i64,
+) -> Result<Vec<CrawlRun>, MyError> {
+    CrawlRunRDB::recent(conn, media.copied(), limit)
+}
+
+#[derive(Debug, Queryable, Insertable, Identifiable, AsChangeset, Clone)]
+#[table_name = "crawl_runs"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct CrawlRunRDB {
+    pub id: String,
+    pub media: Media,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub error: Option<String>,
+    pub pages_fetched: i32,
+    pub articles_inserted: i32,
+    pub articles_updated: i32,
+    pub articles_unchanged: i32,
+    pub articles_removed: i32,
+}
+
+impl CrawlRunRDB {
+    fn store(self, conn: &MysqlConnection) -> Result<(), MyError> {
+        diesel::insert_into(crawl_runs::table)
+            .values(&self)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    fn save(self, conn: &MysqlConnection) -> Result<(), MyError> {
+        diesel::update(&self).set(&self).execute(conn)?;
+        Ok(())
+    }
+
+    fn find(conn: &MysqlConnection, id: &str) -> Result<CrawlRun, MyError> {
+        let record = crawl_runs::table
+            .find(id.to_string())
+            .first::<CrawlRunRDB>(conn)?;
+        Ok(record.to_domain())
+    }
+
+    fn recent(
+        conn: &MysqlConnection,
+        media: Option<Media>,
+        limit: i64,
+    ) -> Result<Vec<CrawlRun>, MyError> {
+        let mut query = crawl_runs::table.into_boxed();
+        if let Some(media) = media {
+            query = query.filter(crawl_runs::media.eq(media));
+        }
+        let records = query
+            .order_by(crawl_runs::started_at.desc())
+            .limit(limit)
+            .load::<CrawlRunRDB>(conn)?;
+        Ok(records.iter().map(|record| record.to_domain()).collect())
+    }
+
+    fn from_domain(run: &CrawlRun) -> CrawlRunRDB {
+        CrawlRunRDB {
+            id: run.id.clone(),
+            media: run.media,
+            started_at: run.started_at.naive_utc(),
+            finished_at: run.finished_at.map(|at| at.naive_utc()),
+            error: run.error.clone(),
+            pages_fetched: run.pages_fetched,
+            articles_inserted: run.articles_inserted,
+            articles_updated: run.articles_updated,
+            articles_unchanged: run.articles_unchanged,
+            articles_removed: run.articles_removed,
+        }
+    }
+
+    fn to_domain(&self) -> CrawlRun {
+        CrawlRun {
+            id: self.id.clone(),
+            media: self.media,
+            started_at: Utc.from_utc_datetime(&self.started_at),
+            finished_at: self.finished_at.map(|at| Utc.from_utc_datetime(&at)),
+            error: self.error.clone(),
+            pages_fetched: self.pages_fetched,
+            articles_inserted: self.articles_inserted,
+            articles_updated: self.articles_updated,
+            articles_unchanged: self.articles_unchanged,
+            articles_removed: self.articles_removed,
+        }
+    }
+}
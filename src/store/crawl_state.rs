@@ -0,0 +1,74 @@
+use crate::crawl::{CrawlState, Media};
+use crate::schema::crawl_state;
+use crate::utils::errors::MyError;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+
+/// mediaの全account、全feedのstateを取得する。一度もcrawlしていなければ[]
+pub fn load(conn: &MysqlConnection, media: &Media) -> Result<Vec<CrawlState>, MyError> {
+    CrawlStateRDB::scan_in_media(conn, media)
+}
+
+#[derive(Debug, Queryable, Insertable, Identifiable, Clone)]
+#[table_name = "crawl_state"]
+#[primary_key(media, account_id, feed)]
+pub struct CrawlStateRDB {
+    pub media: Media,
+    /// 環境変数で設定したaccountは空文字。
+    pub account_id: String,
+    pub feed: String,
+    pub last_item_id: Option<String>,
+    pub next_page_token: Option<String>,
+    pub last_crawled_at: Option<NaiveDateTime>,
+    pub item_count: i32,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl CrawlStateRDB {
+    pub fn store_batch(conn: &MysqlConnection, records: Vec<CrawlStateRDB>) -> Result<(), MyError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        diesel::replace_into(crawl_state::table)
+            .values(records)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    fn scan_in_media(conn: &MysqlConnection, media: &Media) -> Result<Vec<CrawlState>, MyError> {
+        let records = crawl_state::table
+            .filter(crawl_state::media.eq(media))
+            .load::<CrawlStateRDB>(conn)?;
+        Ok(records.iter().map(|record| record.to_domain()).collect())
+    }
+
+    pub fn from_domain(state: &CrawlState) -> CrawlStateRDB {
+        CrawlStateRDB {
+            media: state.media,
+            account_id: state.account_id.clone().unwrap_or_default(),
+            feed: state.feed.clone(),
+            last_item_id: state.last_item_id.clone(),
+            next_page_token: state.next_page_token.clone(),
+            last_crawled_at: state.last_crawled_at.map(|at| at.naive_utc()),
+            item_count: state.item_count,
+            etag: state.etag.clone(),
+            last_modified: state.last_modified.clone(),
+        }
+    }
+
+    fn to_domain(&self) -> CrawlState {
+        CrawlState {
+            media: self.media,
+            account_id: Some(self.account_id.clone()).filter(|id| !id.is_empty()),
+            feed: self.feed.clone(),
+            last_item_id: self.last_item_id.clone(),
+            next_page_token: self.next_page_token.clone(),
+            last_crawled_at: self.last_crawled_at.map(|at| Utc.from_utc_datetime(&at)),
+            item_count: self.item_count,
+            etag: self.etag.clone(),
+            last_modified: self.last_modified.clone(),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::crawl::Media;
+use diesel::deserialize::{self, FromSql};
+use diesel::mysql::Mysql;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Text;
+
+impl ToSql<Text, Mysql> for Media {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Mysql>) -> serialize::Result {
+        ToSql::<Text, Mysql>::to_sql(&self.to_string(), out)
+    }
+}
+
+impl FromSql<Text, Mysql> for Media {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        let s = <String as FromSql<Text, Mysql>>::from_sql(bytes)?;
+        Media::from_str(&s).map_err(|_| format!("unknown media: {}", s).into())
+    }
+}
@@ -1 +1,8 @@
+pub mod article_account;
+pub mod crawl_run;
+pub mod crawl_state;
+mod media;
 pub mod model;
+pub mod oauth_token;
+pub mod source;
+pub mod tag;
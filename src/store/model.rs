@@ -1,41 +1,370 @@
+use std::collections::HashMap;
+use std::iter;
 use std::str::FromStr;
 
-use crate::article::Article;
-use crate::schema::articles;
-use crate::schema::articles::created_at;
+use crate::article::{Article, ArticleFilter, ArticleOrder, TagMatch, TITLE_MAX_CHARS};
+use crate::canonical;
+use crate::crawl::{CrawlState, Media};
+use crate::event::{self, Event};
+use crate::schema::{article_accounts, article_tags, articles, tags};
+use crate::store::crawl_state::CrawlStateRDB;
+use crate::store::{article_account, tag};
 use crate::utils::errors::MyError;
-use chrono::{DateTime, Local, NaiveDate, NaiveDateTime};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use diesel::mysql::Mysql;
 use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Datetime, Double, Integer, Nullable, Text};
 use diesel::MysqlConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use strum_macros::Display;
 
-pub fn store_rdb(conn: &MysqlConnection, records: &Vec<Article>) {
-    let records = records
-        .into_iter()
-        .map(|article| ArticleRDB::from_domain(article))
-        .collect::<Vec<ArticleRDB>>();
-    ArticleRDB::store_batch(&conn, records);
+/// 保存したarticleの件数
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StoreCount {
+    pub inserted: usize,
+    pub updated: usize,
+    /// 保存済みで、sourceの内容も変わっていない。
+    pub unchanged: usize,
 }
+
+/// articleとcrawl stateを同じtransactionで保存する。
+/// 保存済みのarticleはsourceから取得する列だけ更新し、ほかの列は残す。
+/// commitしたら新しく追加されたarticleをpublishする。
+pub fn store_rdb(
+    conn: &MysqlConnection,
+    articles: &Vec<Article>,
+    states: &[CrawlState],
+) -> Result<StoreCount, MyError> {
+    let states = states
+        .iter()
+        .map(CrawlStateRDB::from_domain)
+        .collect::<Vec<CrawlStateRDB>>();
+    let upserted = conn.transaction::<_, MyError, _>(|| {
+        let upserted = articles
+            .iter()
+            .map(|article| ArticleRDB::from_domain(article).upsert(conn))
+            .collect::<Result<Vec<Upserted>, MyError>>()?;
+        tag::store_batch(conn, articles)?;
+        article_account::store_batch(conn, articles)?;
+        CrawlStateRDB::store_batch(conn, states)?;
+        Ok(upserted)
+    })?;
+    let mut count = StoreCount::default();
+    for (article, upserted) in articles.iter().zip(upserted) {
+        match upserted {
+            Upserted::Inserted => {
+                count.inserted += 1;
+                event::publish(Event::ArticleAdded(Box::new(article.clone())));
+            }
+            Upserted::Updated => count.updated += 1,
+            Upserted::Unchanged => count.unchanged += 1,
+        }
+    }
+    Ok(count)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Upserted {
+    Inserted,
+    Updated,
+    Unchanged,
+}
+
+/// urlと同じvarchar(2048)。canonicalizeで長くなって収まらなければ重複としてまとめない。
+const CANONICAL_URL_MAX_CHARS: usize = 2048;
+
+/// 保存済みなら更新するsourceの列。
+/// crawled_atは最初に保存したときのまま。
+const UPSTREAM_COLUMNS: &[&str] = &[
+    "title",
+    "author",
+    "url",
+    "summary",
+    "discussion_url",
+    "score",
+    "comment_count",
+    "thumbnail_url",
+    "duration_seconds",
+    "view_count",
+    "unavailable",
+    "metadata",
+    "canonical_url",
+    // sourceで再び見えたら戻す。
+    "removed_at",
+];
+
+/// 同じcanonical urlのarticle。保存した古い順。sourceで外されたものは除く。
+pub fn find_by_canonical_url(
+    conn: &MysqlConnection,
+    canonical_url: &str,
+) -> Result<Vec<Article>, MyError> {
+    let records = articles::table
+        .filter(articles::canonical_url.eq(canonical_url))
+        .filter(articles::removed_at.is_null())
+        .order_by((articles::crawled_at.asc(), articles::id.asc()))
+        .load::<ArticleRDB>(conn)?;
+    with_tags_and_accounts(
+        conn,
+        records.iter().map(|record| record.to_domain()).collect(),
+    )
+}
+
+/// accountが保存したarticleのうち、source_idsにないものをそのaccountで外されたとして記録する。
+/// ほかのaccountがまだ保存していなければarticleも外されたとして記録し、その件数を返す。
+pub fn mark_removed(
+    conn: &MysqlConnection,
+    media: Media,
+    account_id: Option<&str>,
+    source_ids: &[String],
+    removed_at: DateTime<Utc>,
+) -> Result<usize, MyError> {
+    conn.transaction::<_, MyError, _>(|| {
+        let saved = article_accounts::table
+            .filter(article_accounts::account_id.eq(account_id.unwrap_or_default().to_string()))
+            .filter(article_accounts::removed_at.is_null())
+            .select(article_accounts::article_id);
+        let missing = articles::table
+            .filter(articles::media.eq(media))
+            .filter(articles::source_id.ne_all(source_ids.to_vec()))
+            .filter(articles::id.eq_any(saved))
+            .select(articles::id)
+            .load::<String>(conn)?;
+        if missing.is_empty() {
+            return Ok(0);
+        }
+        article_account::mark_removed(conn, account_id, &missing, removed_at)?;
+        let still_saved = article_accounts::table
+            .filter(article_accounts::removed_at.is_null())
+            .select(article_accounts::article_id);
+        let count = diesel::update(articles::table)
+            .filter(articles::id.eq_any(missing))
+            .filter(articles::removed_at.is_null())
+            .filter(articles::id.ne_all(still_saved))
+            .set(articles::removed_at.eq(Some(removed_at.naive_utc())))
+            .execute(conn)?;
+        Ok(count)
+    })
+}
+
+/// canonical_urlを追加する前に保存したarticleを埋める。埋めた件数を返す。
+pub fn fill_canonical_urls(conn: &MysqlConnection) -> Result<usize, MyError> {
+    let records = articles::table
+        .filter(articles::canonical_url.eq(""))
+        .select((articles::id, articles::url))
+        .load::<(String, String)>(conn)?;
+    conn.transaction::<_, MyError, _>(|| {
+        for (id, url) in records.iter() {
+            diesel::update(articles::table.find(id))
+                .set(articles::canonical_url.eq(canonical::canonicalize(url)))
+                .execute(conn)?;
+        }
+        Ok(())
+    })?;
+    Ok(records.len())
+}
+
 pub fn scan(conn: &MysqlConnection) -> Result<Vec<Article>, MyError> {
     let records = ArticleRDB::scan(&conn);
     records
 }
 
-pub fn latest_one(conn: &MysqlConnection, media: &str) -> Result<Article, MyError> {
-    ArticleRDB::latest_one_in_media(conn, media)
+/// pageの位置。並び順のkeyそのもの。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArticleCursor {
+    pub created_at: i64,
+    pub id: String,
+}
+
+/// cursorより後ろをlimit件取得する。
+pub fn page(
+    conn: &MysqlConnection,
+    filter: &ArticleFilter,
+    order: ArticleOrder,
+    after: Option<&ArticleCursor>,
+    limit: i64,
+) -> Result<Vec<(ArticleCursor, Article)>, MyError> {
+    ArticleRDB::page(conn, filter, order, after, limit)
+}
+
+/// cursorに関係なく、filterに一致する件数
+pub fn count(conn: &MysqlConnection, filter: &ArticleFilter) -> Result<i64, MyError> {
+    ArticleRDB::count(conn, filter)
+}
+
+/// fulltext_indexの列と一致させる。
+const MATCH_AGAINST: &str = "MATCH(title, summary, author) AGAINST (? IN NATURAL LANGUAGE MODE)";
+
+/// 関連度の高い順にoffsetからlimit件取得する。
+pub fn search(
+    conn: &MysqlConnection,
+    query: &str,
+    media: Option<Media>,
+    include_removed: bool,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<(f64, Article)>, MyError> {
+    let records = diesel::sql_query(format!(
+        "SELECT *, {match_against} AS relevance FROM articles \
+         WHERE {match_against} AND (? IS NULL OR media = ?) \
+         AND (? OR removed_at IS NULL) \
+         ORDER BY relevance DESC, id ASC LIMIT ? OFFSET ?",
+        match_against = MATCH_AGAINST
+    ))
+    .bind::<Text, _>(query)
+    .bind::<Text, _>(query)
+    .bind::<Nullable<Text>, _>(media.map(|media| media.to_string()))
+    .bind::<Nullable<Text>, _>(media.map(|media| media.to_string()))
+    .bind::<Bool, _>(include_removed)
+    .bind::<BigInt, _>(limit)
+    .bind::<BigInt, _>(offset)
+    .load::<SearchRecord>(conn)?;
+    let scores = records
+        .iter()
+        .map(|record| record.relevance)
+        .collect::<Vec<f64>>();
+    let articles = with_tags_and_accounts(
+        conn,
+        records
+            .into_iter()
+            .map(|record| record.article.to_domain())
+            .collect(),
+    )?;
+    let articles = with_same_page(conn, articles)?;
+    Ok(scores.into_iter().zip(articles).collect())
+}
+
+/// article_tagsからtag、article_accountsから保存したaccountを埋める。
+fn with_tags_and_accounts(
+    conn: &MysqlConnection,
+    mut articles: Vec<Article>,
+) -> Result<Vec<Article>, MyError> {
+    let ids = articles
+        .iter()
+        .map(|article| article.id.clone())
+        .collect::<Vec<String>>();
+    let mut tags = tag::load(conn, &ids)?;
+    let mut accounts = article_account::load(conn, &ids)?;
+    for article in articles.iter_mut() {
+        article.tags = tags.remove(&article.id).unwrap_or_default();
+        article.account_ids = accounts.remove(&article.id).unwrap_or_default();
+    }
+    Ok(articles)
 }
 
-#[derive(Debug, Queryable, Insertable, Identifiable, Clone)]
+/// 同じcanonical urlのほかのarticleをまとめて埋める。sourceで外されたものは除く。
+/// ほかのarticleにも、そのarticleから見た同じページを埋める。
+fn with_same_page(
+    conn: &MysqlConnection,
+    mut articles: Vec<Article>,
+) -> Result<Vec<Article>, MyError> {
+    let canonical_urls = articles
+        .iter()
+        .filter_map(|article| article.canonical_url.clone())
+        .filter(|canonical_url| !canonical_url.is_empty())
+        .collect::<Vec<String>>();
+    let mut pages = HashMap::<String, Vec<Article>>::new();
+    if !canonical_urls.is_empty() {
+        let records = articles::table
+            .filter(articles::canonical_url.eq_any(canonical_urls))
+            .filter(articles::removed_at.is_null())
+            .order_by((articles::crawled_at.asc(), articles::id.asc()))
+            .load::<ArticleRDB>(conn)?;
+        let saved = with_tags_and_accounts(
+            conn,
+            records.iter().map(|record| record.to_domain()).collect(),
+        )?;
+        for article in saved {
+            pages
+                .entry(article.canonical_url.clone().unwrap_or_default())
+                .or_default()
+                .push(article);
+        }
+    }
+    let others = |page: &[Article], id: &str| {
+        page.iter()
+            .filter(|article| article.id != id)
+            .cloned()
+            .collect::<Vec<Article>>()
+    };
+    for article in articles.iter_mut() {
+        let page = article
+            .canonical_url
+            .as_ref()
+            .and_then(|canonical_url| pages.get(canonical_url))
+            .map(|page| page.as_slice())
+            .unwrap_or_default();
+        article.same_page = Some(
+            others(page, &article.id)
+                .into_iter()
+                .map(|other| Article {
+                    same_page: Some(others(page, &other.id)),
+                    ..other
+                })
+                .collect(),
+        );
+    }
+    Ok(articles)
+}
+
+pub fn search_count(
+    conn: &MysqlConnection,
+    query: &str,
+    media: Option<Media>,
+    include_removed: bool,
+) -> Result<i64, MyError> {
+    let record = diesel::sql_query(format!(
+        "SELECT COUNT(*) AS count FROM articles \
+         WHERE {} AND (? IS NULL OR media = ?) \
+         AND (? OR removed_at IS NULL)",
+        MATCH_AGAINST
+    ))
+    .bind::<Text, _>(query)
+    .bind::<Nullable<Text>, _>(media.map(|media| media.to_string()))
+    .bind::<Nullable<Text>, _>(media.map(|media| media.to_string()))
+    .bind::<Bool, _>(include_removed)
+    .get_result::<CountRecord>(conn)?;
+    Ok(record.count)
+}
+
+#[derive(Debug, QueryableByName)]
+struct SearchRecord {
+    #[diesel(embed)]
+    article: ArticleRDB,
+    /// articlesのscoreと名前が重ならないようにする。
+    #[sql_type = "Double"]
+    relevance: f64,
+}
+
+#[derive(Debug, QueryableByName)]
+struct CountRecord {
+    #[sql_type = "BigInt"]
+    count: i64,
+}
+
+#[derive(Debug, Queryable, QueryableByName, Insertable, Identifiable, Clone)]
 #[table_name = "articles"]
 pub struct ArticleRDB {
     pub id: String,
     pub title: String,
     pub author: String,
-    pub media: String,
+    pub media: Media,
     pub url: String,
     pub summary: String,
     pub created_at: NaiveDateTime,
     pub crawled_at: NaiveDateTime,
+    pub discussion_url: Option<String>,
+    pub score: Option<i32>,
+    pub comment_count: Option<i32>,
+    pub thumbnail_url: Option<String>,
+    pub duration_seconds: Option<i32>,
+    pub view_count: Option<i64>,
+    pub unavailable: bool,
+    /// ArticleMetadataのjson
+    pub metadata: Option<String>,
+    pub canonical_url: String,
+    pub source_id: String,
+    pub removed_at: Option<NaiveDateTime>,
 }
 
 impl ArticleRDB {
@@ -45,43 +374,217 @@ impl ArticleRDB {
             .execute(conn)?;
         Ok(())
     }
-    fn store_batch(conn: &MysqlConnection, records: Vec<ArticleRDB>) -> Result<(), MyError> {
-        diesel::replace_into(articles::table)
-            .values(records)
-            .execute(conn)?;
-        Ok(())
-    }
-    pub fn latest_one_in_media(conn: &MysqlConnection, media: &str) -> Result<Article, MyError> {
-        // そこまでのデータ数にはならないので、indexで対応する。
-        // データが多くなりそうなら、latestテーブルなどを検討する。
-        let record = articles::table
-            .filter(articles::media.eq(media.to_string()))
-            .order_by(created_at.desc())
-            .first::<ArticleRDB>(conn)?;
-        Ok(record.to_domain())
+    /// INSERT ... ON DUPLICATE KEY UPDATE。
+    /// affected rowsは追加なら1、更新なら2、値が変わらなければ0。
+    fn upsert(&self, conn: &MysqlConnection) -> Result<Upserted, MyError> {
+        let updates = UPSTREAM_COLUMNS
+            .iter()
+            .map(|column| format!("{column} = VALUES({column})", column = column))
+            // 日時のないfeedのitemはcrawlのたびにcrawled_atになるので、早い方を残す。
+            .chain(iter::once(
+                "created_at = LEAST(created_at, VALUES(created_at))".to_string(),
+            ))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let affected = diesel::sql_query(format!(
+            "INSERT INTO articles (id, title, author, media, url, summary, created_at, \
+             crawled_at, discussion_url, score, comment_count, thumbnail_url, \
+             duration_seconds, view_count, unavailable, metadata, canonical_url, \
+             source_id, removed_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+             ON DUPLICATE KEY UPDATE {}",
+            updates
+        ))
+        .bind::<Text, _>(&self.id)
+        .bind::<Text, _>(&self.title)
+        .bind::<Text, _>(&self.author)
+        .bind::<Text, _>(self.media.to_string())
+        .bind::<Text, _>(&self.url)
+        .bind::<Text, _>(&self.summary)
+        .bind::<Datetime, _>(self.created_at)
+        .bind::<Datetime, _>(self.crawled_at)
+        .bind::<Nullable<Text>, _>(&self.discussion_url)
+        .bind::<Nullable<Integer>, _>(self.score)
+        .bind::<Nullable<Integer>, _>(self.comment_count)
+        .bind::<Nullable<Text>, _>(&self.thumbnail_url)
+        .bind::<Nullable<Integer>, _>(self.duration_seconds)
+        .bind::<Nullable<BigInt>, _>(self.view_count)
+        .bind::<Bool, _>(self.unavailable)
+        .bind::<Nullable<Text>, _>(&self.metadata)
+        .bind::<Text, _>(&self.canonical_url)
+        .bind::<Text, _>(&self.source_id)
+        .bind::<Nullable<Datetime>, _>(self.removed_at)
+        .execute(conn)?;
+        Ok(match affected {
+            0 => Upserted::Unchanged,
+            1 => Upserted::Inserted,
+            _ => Upserted::Updated,
+        })
     }
-
     fn scan(conn: &MysqlConnection) -> Result<Vec<Article>, MyError> {
         let articlerdbs = articles::table.load::<ArticleRDB>(conn)?;
         let articles = articlerdbs
             .into_iter()
             .map(|articlerdb| articlerdb.to_domain())
             .collect::<Vec<Article>>();
-        Ok(articles)
+        with_tags_and_accounts(conn, articles)
+    }
+
+    /// media_index、created_at_indexが効くように絞り込む。
+    fn filtered(filter: &ArticleFilter) -> articles::BoxedQuery<'static, Mysql> {
+        let mut query = articles::table.into_boxed();
+        if let Some(media) = &filter.media {
+            query = query.filter(articles::media.eq(*media));
+        }
+        if let Some(author) = &filter.author {
+            query = query.filter(articles::author.eq(author.clone()));
+        }
+        if let Some(created_from) = filter.created_from {
+            query = query.filter(articles::created_at.ge(created_from.naive_utc()));
+        }
+        if let Some(created_to) = filter.created_to {
+            query = query.filter(articles::created_at.le(created_to.naive_utc()));
+        }
+        if !filter.include_removed {
+            query = query.filter(articles::removed_at.is_null());
+        }
+        if !filter.tags.is_empty() {
+            let tagged = |names: Vec<String>| {
+                article_tags::table
+                    .inner_join(tags::table)
+                    .filter(tags::name.eq_any(names))
+                    .select(article_tags::article_id)
+            };
+            query = match filter.tag_match {
+                // tagごとにそのtagのついたarticleに絞る。
+                TagMatch::All => filter.tags.iter().fold(query, |query, tag| {
+                    query.filter(articles::id.eq_any(tagged(vec![tag.clone()])))
+                }),
+                TagMatch::Any => query.filter(articles::id.eq_any(tagged(filter.tags.clone()))),
+            };
+        }
+        query
+    }
+
+    /// group_duplicatesなら、同じcanonical urlのうち最初に保存したものだけにする。
+    /// mediaやauthorで絞っているときは、その中で最初に保存したもの。
+    fn grouped(filter: &ArticleFilter) -> articles::BoxedQuery<'static, Mysql> {
+        let query = Self::filtered(filter);
+        if !filter.group_duplicates {
+            return query;
+        }
+        let mut earlier = vec![
+            "a2.canonical_url = articles.canonical_url",
+            "(a2.crawled_at < articles.crawled_at \
+             OR (a2.crawled_at = articles.crawled_at AND a2.id < articles.id))",
+        ];
+        if filter.media.is_some() {
+            earlier.push("a2.media = articles.media");
+        }
+        if filter.author.is_some() {
+            earlier.push("a2.author = articles.author");
+        }
+        if !filter.include_removed {
+            earlier.push("a2.removed_at IS NULL");
+        }
+        query.filter(
+            articles::canonical_url
+                .eq("")
+                .or(diesel::dsl::sql::<Bool>(&format!(
+                    "NOT EXISTS (SELECT 1 FROM articles a2 WHERE {})",
+                    earlier.join(" AND ")
+                ))),
+        )
+    }
+
+    fn page(
+        conn: &MysqlConnection,
+        filter: &ArticleFilter,
+        order: ArticleOrder,
+        after: Option<&ArticleCursor>,
+        limit: i64,
+    ) -> Result<Vec<(ArticleCursor, Article)>, MyError> {
+        let mut query = Self::grouped(filter);
+        // created_atが同じものはidで順序を決める。
+        if let Some(after) = after {
+            let created_at = DateTime::from_timestamp(after.created_at, 0)
+                .map(|created_at| created_at.naive_utc())
+                .ok_or_else(|| MyError::BadRequest(json!({"error":"invalid cursor"})))?;
+            let id = after.id.clone();
+            query = match order {
+                ArticleOrder::CreatedAtDesc => query.filter(
+                    articles::created_at
+                        .lt(created_at)
+                        .or(articles::created_at.eq(created_at).and(articles::id.lt(id))),
+                ),
+                ArticleOrder::CreatedAtAsc => query.filter(
+                    articles::created_at
+                        .gt(created_at)
+                        .or(articles::created_at.eq(created_at).and(articles::id.gt(id))),
+                ),
+            };
+        }
+        query = match order {
+            ArticleOrder::CreatedAtDesc => {
+                query.order_by((articles::created_at.desc(), articles::id.desc()))
+            }
+            ArticleOrder::CreatedAtAsc => {
+                query.order_by((articles::created_at.asc(), articles::id.asc()))
+            }
+        };
+        let records = query.limit(limit).load::<ArticleRDB>(conn)?;
+        let cursors = records
+            .iter()
+            .map(|record| ArticleCursor {
+                created_at: record.created_at.and_utc().timestamp(),
+                id: record.id.clone(),
+            })
+            .collect::<Vec<ArticleCursor>>();
+        let articles = with_tags_and_accounts(
+            conn,
+            records.iter().map(|record| record.to_domain()).collect(),
+        )?;
+        let articles = with_same_page(conn, articles)?;
+        Ok(cursors.into_iter().zip(articles).collect())
+    }
+
+    fn count(conn: &MysqlConnection, filter: &ArticleFilter) -> Result<i64, MyError> {
+        let count = Self::grouped(filter).count().get_result::<i64>(conn)?;
+        Ok(count)
     }
 
     fn from_domain(article: &Article) -> ArticleRDB {
         ArticleRDB {
             id: article.id.clone(),
-            title: article.title.clone(),
+            // 長いtitleが1件あるとtransactionごと失敗するので、全mediaでここで切る。
+            title: article.title.chars().take(TITLE_MAX_CHARS).collect(),
             author: article.author.clone(),
-            media: article.media.clone(),
+            media: article.media,
             url: article.url.clone(),
             summary: article.summary.clone(),
-            created_at: NaiveDateTime::parse_from_str(&article.created_at, "%Y-%m-%d %H:%M:%S")
-                .unwrap(),
-            crawled_at: NaiveDateTime::parse_from_str(&article.crawled_at, "%Y-%m-%d %H:%M:%S%.9f")
-                .unwrap(),
+            created_at: article.created_at.naive_utc(),
+            crawled_at: article.crawled_at.naive_utc(),
+            discussion_url: article.discussion_url.clone(),
+            score: article.score,
+            comment_count: article.comment_count,
+            thumbnail_url: article.thumbnail_url.clone(),
+            duration_seconds: article.duration_seconds,
+            view_count: article.view_count,
+            unavailable: article.unavailable,
+            metadata: article
+                .metadata
+                .as_ref()
+                .and_then(|metadata| serde_json::to_string(metadata).ok()),
+            canonical_url: Some(
+                article
+                    .canonical_url
+                    .clone()
+                    .unwrap_or_else(|| canonical::canonicalize(&article.url)),
+            )
+            .filter(|canonical_url| canonical_url.chars().count() <= CANONICAL_URL_MAX_CHARS)
+            .unwrap_or_default(),
+            source_id: article.source_id.clone(),
+            removed_at: article.removed_at.map(|at| at.naive_utc()),
         }
     }
 
@@ -90,11 +593,29 @@ impl ArticleRDB {
             id: self.id.clone(),
             title: self.title.clone(),
             author: self.author.clone(),
-            media: self.media.clone(),
+            media: self.media,
             url: self.url.clone(),
             summary: self.summary.clone(),
-            created_at: self.created_at.to_string(),
-            crawled_at: self.crawled_at.to_string(),
+            created_at: Utc.from_utc_datetime(&self.created_at),
+            crawled_at: Utc.from_utc_datetime(&self.crawled_at),
+            discussion_url: self.discussion_url.clone(),
+            score: self.score,
+            comment_count: self.comment_count,
+            thumbnail_url: self.thumbnail_url.clone(),
+            duration_seconds: self.duration_seconds,
+            view_count: self.view_count,
+            unavailable: self.unavailable,
+            metadata: self
+                .metadata
+                .as_ref()
+                .and_then(|metadata| serde_json::from_str(metadata).ok()),
+            tags: vec![],
+            account_id: None,
+            account_ids: vec![],
+            same_page: None,
+            canonical_url: Some(self.canonical_url.clone()),
+            source_id: self.source_id.clone(),
+            removed_at: self.removed_at.map(|at| Utc.from_utc_datetime(&at)),
         }
     }
 }
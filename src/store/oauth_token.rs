@@ -0,0 +1,62 @@
+use crate::crawl::oauth::OAuthToken;
+use crate::crawl::Media;
+use crate::schema::oauth_tokens;
+use crate::utils::errors::MyError;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+
+/// 一度も認可していなければNone
+pub fn find(conn: &MysqlConnection, media: &Media) -> Result<Option<OAuthToken>, MyError> {
+    OAuthTokenRDB::find(conn, media)
+}
+
+/// 同じmediaのtokenは上書きする。
+pub fn store(conn: &MysqlConnection, token: &OAuthToken) -> Result<(), MyError> {
+    OAuthTokenRDB::from_domain(token).store(conn)
+}
+
+#[derive(Debug, Queryable, Insertable, Identifiable, Clone)]
+#[table_name = "oauth_tokens"]
+#[primary_key(media)]
+pub struct OAuthTokenRDB {
+    pub media: Media,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+impl OAuthTokenRDB {
+    fn store(self, conn: &MysqlConnection) -> Result<(), MyError> {
+        diesel::replace_into(oauth_tokens::table)
+            .values(&self)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    fn find(conn: &MysqlConnection, media: &Media) -> Result<Option<OAuthToken>, MyError> {
+        let record = oauth_tokens::table
+            .filter(oauth_tokens::media.eq(media))
+            .first::<OAuthTokenRDB>(conn)
+            .optional()?;
+        Ok(record.map(|record| record.to_domain()))
+    }
+
+    fn from_domain(token: &OAuthToken) -> OAuthTokenRDB {
+        OAuthTokenRDB {
+            media: token.media,
+            access_token: token.access_token.clone(),
+            refresh_token: token.refresh_token.clone(),
+            expires_at: token.expires_at.naive_utc(),
+        }
+    }
+
+    fn to_domain(&self) -> OAuthToken {
+        OAuthToken {
+            media: self.media,
+            access_token: self.access_token.clone(),
+            refresh_token: self.refresh_token.clone(),
+            expires_at: Utc.from_utc_datetime(&self.expires_at),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::crawl::{Media, Source};
+use crate::schema::sources;
+use crate::utils::errors::MyError;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+
+/// mediaを省略すると全media。登録順。
+pub fn all(conn: &MysqlConnection, media: Option<&Media>) -> Result<Vec<Source>, MyError> {
+    SourceRDB::scan(conn, media)
+}
+
+pub fn insert(conn: &MysqlConnection, source: &Source) -> Result<(), MyError> {
+    SourceRDB::from_domain(source).store(conn)
+}
+
+/// 削除したらtrue。articleは残す。
+pub fn delete(conn: &MysqlConnection, id: &str) -> Result<bool, MyError> {
+    let count = diesel::delete(sources::table.filter(sources::id.eq(id))).execute(conn)?;
+    Ok(count > 0)
+}
+
+#[derive(Debug, Queryable, Insertable, Identifiable, Clone)]
+#[table_name = "sources"]
+pub struct SourceRDB {
+    pub id: String,
+    pub media: Media,
+    pub label: String,
+    pub account: String,
+    pub credential: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl SourceRDB {
+    fn store(self, conn: &MysqlConnection) -> Result<(), MyError> {
+        diesel::insert_into(sources::table)
+            .values(&self)
+            .execute(conn)?;
+        Ok(())
+    }
+
+    fn scan(conn: &MysqlConnection, media: Option<&Media>) -> Result<Vec<Source>, MyError> {
+        let mut query = sources::table.into_boxed();
+        if let Some(media) = media {
+            query = query.filter(sources::media.eq(media));
+        }
+        let records = query
+            .order(sources::created_at.asc())
+            .load::<SourceRDB>(conn)?;
+        Ok(records.iter().map(|record| record.to_domain()).collect())
+    }
+
+    fn from_domain(source: &Source) -> SourceRDB {
+        SourceRDB {
+            id: source.id.clone(),
+            media: source.media,
+            label: source.label.clone(),
+            account: source.account.clone(),
+            credential: source.credential.clone(),
+            created_at: source.created_at.naive_utc(),
+        }
+    }
+
+    fn to_domain(&self) -> Source {
+        Source {
+            id: self.id.clone(),
+            media: self.media,
+            label: self.label.clone(),
+            account: self.account.clone(),
+            credential: self.credential.clone(),
+            created_at: Utc.from_utc_datetime(&self.created_at),
+        }
+    }
+}
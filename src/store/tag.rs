@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::article::Article;
+use crate::schema::{article_tags, tags};
+use crate::utils::errors::MyError;
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+
+/// article idごとのtag。tagのないarticleは含まない。
+pub fn load(
+    conn: &MysqlConnection,
+    article_ids: &[String],
+) -> Result<HashMap<String, Vec<String>>, MyError> {
+    if article_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = article_tags::table
+        .inner_join(tags::table)
+        .filter(article_tags::article_id.eq_any(article_ids))
+        .order_by((article_tags::article_id, tags::name))
+        .select((article_tags::article_id, tags::name))
+        .load::<(String, String)>(conn)?;
+    let mut tags = HashMap::<String, Vec<String>>::new();
+    for (article_id, name) in rows {
+        tags.entry(article_id).or_default().push(name);
+    }
+    Ok(tags)
+}
+
+/// tagのあるarticleはtagを置き換える。
+/// sourceにtagがないarticleは[]なので、保存済みのtagを残す。
+pub fn store_batch(conn: &MysqlConnection, articles: &[Article]) -> Result<(), MyError> {
+    let tagged = articles
+        .iter()
+        .filter(|article| !article.tags.is_empty())
+        .collect::<Vec<&Article>>();
+    if tagged.is_empty() {
+        return Ok(());
+    }
+    let ids = TagRDB::ensure(
+        conn,
+        tagged
+            .iter()
+            .flat_map(|article| article.tags.iter().cloned()),
+    )?;
+    let records = tagged
+        .iter()
+        .flat_map(|article| {
+            article.tags.iter().filter_map(|tag| {
+                ids.get(&tag.to_lowercase()).map(|tag_id| ArticleTagRDB {
+                    article_id: article.id.clone(),
+                    tag_id: *tag_id,
+                })
+            })
+        })
+        .collect::<Vec<ArticleTagRDB>>();
+    let article_ids = tagged
+        .iter()
+        .map(|article| article.id.clone())
+        .collect::<Vec<String>>();
+    diesel::delete(article_tags::table.filter(article_tags::article_id.eq_any(article_ids)))
+        .execute(conn)?;
+    diesel::insert_or_ignore_into(article_tags::table)
+        .values(records)
+        .execute(conn)?;
+    Ok(())
+}
+
+#[derive(Debug, Queryable, Identifiable, Clone)]
+#[table_name = "tags"]
+pub struct TagRDB {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "tags"]
+struct NewTagRDB {
+    name: String,
+}
+
+impl TagRDB {
+    /// なければ追加して、小文字にしたnameからidを引けるようにする。
+    /// nameの比較はDBのcollationに合わせて大文字小文字を区別しない。
+    fn ensure<I: Iterator<Item = String>>(
+        conn: &MysqlConnection,
+        names: I,
+    ) -> Result<HashMap<String, i64>, MyError> {
+        let mut names = names.collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        let records = names
+            .iter()
+            .map(|name| NewTagRDB { name: name.clone() })
+            .collect::<Vec<NewTagRDB>>();
+        diesel::insert_or_ignore_into(tags::table)
+            .values(records)
+            .execute(conn)?;
+        let tags = tags::table
+            .filter(tags::name.eq_any(names))
+            .load::<TagRDB>(conn)?;
+        Ok(tags
+            .into_iter()
+            .map(|tag| (tag.name.to_lowercase(), tag.id))
+            .collect())
+    }
+}
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "article_tags"]
+pub struct ArticleTagRDB {
+    pub article_id: String,
+    pub tag_id: i64,
+}